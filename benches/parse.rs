@@ -0,0 +1,47 @@
+use criterion::criterion_group;
+use criterion::criterion_main;
+use criterion::Criterion;
+use criterion::Throughput;
+
+/// Roughly 10MB of make-like output where one line out of four is a
+/// diagnostic the parser must extract.
+fn synthetic_log() -> String {
+  (0..200_000)
+    .map(|n| match n % 4 {
+      0 => format!(
+        "src/module_{}.c:{}:{}: warning: unused variable 'x'\n",
+        n,
+        n,
+        n % 80
+      ),
+      1 => format!("cc -c -o build/module_{}.o src/module_{}.c\n", n, n),
+      2 => format!(
+        "src/module_{}.c:{}:{}: error: expected ';' before '}}'\n",
+        n,
+        n,
+        n % 80
+      ),
+      _ => String::from("make[1]: Entering directory '/home/user/project'\n"),
+    })
+    .collect()
+}
+
+fn bench_parse(c: &mut Criterion) {
+  let input = synthetic_log();
+  let mut group = c.benchmark_group("parse");
+  group.sample_size(10);
+  group.throughput(Throughput::Bytes(input.len() as u64));
+  group.bench_function("passthrough", |b| {
+    b.iter(|| {
+      errfmt::run(
+        input.clone(),
+        String::from(errfmt::PASSTHROUGH_ERRFMT),
+        String::new(),
+      )
+    })
+  });
+  group.finish();
+}
+
+criterion_group!(benches, bench_parse);
+criterion_main!(benches);
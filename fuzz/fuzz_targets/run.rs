@@ -0,0 +1,11 @@
+#![no_main]
+use libfuzzer_sys::fuzz_target;
+
+// The first line is the errorformat string, the rest is the input.
+fuzz_target!(|data: &[u8]| {
+  if let Ok(data) = std::str::from_utf8(data) {
+    let (errfmt, input) = data.split_once('\n').unwrap_or((data, ""));
+    let _ = errfmt::run(input, errfmt, "");
+    let _ = errfmt::parse(input, errfmt, "");
+  }
+});
@@ -0,0 +1,96 @@
+use regex::Regex;
+
+/// Remove ANSI escape sequences from the input: CSI sequences (colors
+/// of any depth, cursor moves...), OSC sequences (hyperlinks such as
+/// gcc's links to warning options, window titles) terminated by BEL or
+/// ST, and character set selections (`tput sgr0` emits `ESC(B`). Their
+/// 8-bit introducers are recognized too. Only complete sequences
+/// starting with an escape character are removed so that look-alike
+/// text such as `[31m` is left untouched, an OSC sequence never spans
+/// lines.
+pub fn strip_ansi(input: String) -> String {
+  lazy_static! {
+    static ref RE: Regex = Regex::new(concat!(
+      r"(?:\x1b\[|\x{9b})[0-?]*[ -/]*[@-~]",
+      r"|(?:\x1b\]|\x{9d})[^\x07\x1b\x{9c}\n]*(?:\x07|\x1b\\|\x{9c})",
+      r"|\x1b[()*+][0-9A-Za-z]",
+    ))
+    .unwrap();
+  }
+  RE.replace_all(&input, "").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_plain_input_is_untouched() {
+    let input = String::from("/tmp/foo.c:1:2: error: syntax error");
+    assert_eq!(input.clone(), strip_ansi(input))
+  }
+
+  #[test]
+  fn test_colorized_gcc_output() {
+    let input = String::from(
+      "\x1b[01m\x1b[K/tmp/foo.c:3:5:\x1b[m\x1b[K \x1b[01;31m\x1b[Kerror: \x1b[m\x1b[Kexpected ';'",
+    );
+    let expected = String::from("/tmp/foo.c:3:5: error: expected ';'");
+    assert_eq!(expected, strip_ansi(input))
+  }
+
+  #[test]
+  fn test_extended_colors() {
+    let tests = vec![
+      "\x1b[38;5;196merror\x1b[0m",
+      "\x1b[38;2;255;0;0merror\x1b[39m",
+      "\x1b[38:2::255:0:0merror\x1b[39m",
+      "\x1b[1;48;5;236merror\x1b[49;22m",
+    ];
+    for input in tests {
+      assert_eq!("error", strip_ansi(String::from(input)), "{:?}", input);
+    }
+  }
+
+  #[test]
+  fn test_hyperlinks() {
+    let tests = vec![
+      "[\x1b]8;;https://gcc.gnu.org/\x07-Wunused\x1b]8;;\x07]",
+      "[\x1b]8;id=1;https://gcc.gnu.org/\x1b\\-Wunused\x1b]8;;\x1b\\]",
+      "[\u{9d}8;;https://gcc.gnu.org/\u{9c}-Wunused\u{9d}8;;\u{9c}]",
+    ];
+    for input in tests {
+      assert_eq!("[-Wunused]", strip_ansi(String::from(input)), "{:?}", input);
+    }
+  }
+
+  #[test]
+  fn test_window_title() {
+    let input = String::from("\x1b]0;make\x07foo.c:1:2: error: bar");
+    assert_eq!("foo.c:1:2: error: bar", strip_ansi(input))
+  }
+
+  #[test]
+  fn test_8_bit_csi() {
+    let input = String::from("\u{9b}31merror\u{9b}0m");
+    assert_eq!("error", strip_ansi(input))
+  }
+
+  #[test]
+  fn test_character_set_selection() {
+    let input = String::from("\x1b[1merror\x1b(B\x1b[m: foo");
+    assert_eq!("error: foo", strip_ansi(input))
+  }
+
+  #[test]
+  fn test_unterminated_osc_is_preserved() {
+    let input = String::from("\x1b]8;;foo\nbar.c:1:2: error: baz\x07");
+    assert_eq!(input.clone(), strip_ansi(input))
+  }
+
+  #[test]
+  fn test_look_alike_sequences_are_preserved() {
+    let input = String::from("/tmp/[31m/foo.c");
+    assert_eq!(input.clone(), strip_ansi(input))
+  }
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+/// Lines added by a change, as described by a unified diff. Paths are
+/// relative to the root of the repository.
+#[derive(Debug, Default)]
+pub struct Changes {
+  ranges: HashMap<PathBuf, Vec<(u32, u32)>>,
+}
+
+impl Changes {
+  /// Only new file names and hunk headers matter: context is not needed
+  /// and the content of the hunks is skipped (see `git diff -U0`).
+  pub fn parse(diff: &str) -> Self {
+    let mut changes = Self::default();
+    let mut file = None;
+    for line in diff.lines() {
+      if let Some(path) = line.strip_prefix("+++ ") {
+        file = path.strip_prefix("b/").map(PathBuf::from);
+      } else if let (Some(file), Some(range)) = (&file, hunk_range(line)) {
+        changes
+          .ranges
+          .entry(file.clone())
+          .or_insert_with(Vec::new)
+          .push(range);
+      }
+    }
+    changes
+  }
+
+  /// Whether the given line of the given file was added.
+  pub fn contains(&self, file: &Path, line: u32) -> bool {
+    self.ranges.get(file).is_some_and(|ranges| {
+      ranges
+        .iter()
+        .any(|(start, end)| (*start..=*end).contains(&line))
+    })
+  }
+}
+
+/// Read the new side of a hunk header: `@@ -1,2 +3,4 @@` spans lines 3
+/// to 6. A hunk that only removes lines has no added range, and neither
+/// does one ending beyond the last representable line.
+fn hunk_range(line: &str) -> Option<(u32, u32)> {
+  let new = line
+    .strip_prefix("@@ ")?
+    .split(' ')
+    .find(|part| part.starts_with('+'))?;
+  let mut numbers = new[1..].splitn(2, ',').map(str::parse::<u32>);
+  let start = numbers.next()?.ok()?;
+  let count = numbers.next().unwrap_or(Ok(1)).ok()?;
+  match count {
+    0 => None,
+    count => Some((start, start.checked_add(count - 1)?)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const DIFF: &str = "\
+diff --git a/src/foo.c b/src/foo.c
+--- a/src/foo.c
++++ b/src/foo.c
+@@ -3 +3 @@ int main() {
+-  return 1;
++  return 0;
+@@ -10,0 +11,3 @@ int main() {
++  a();
++  b();
++  c();
+@@ -20,2 +22,0 @@ int main() {
+-  d();
+-  e();
+diff --git a/bar.c b/bar.c
+deleted file mode 100644
+--- a/bar.c
++++ /dev/null
+@@ -1 +0,0 @@
+-int x;
+";
+
+  #[test]
+  fn test_added_lines() {
+    let sut = Changes::parse(DIFF);
+    let file = Path::new("src/foo.c");
+    let lines: Vec<_> = (1..25).filter(|line| sut.contains(file, *line)).collect();
+    assert_eq!(vec![3, 11, 12, 13], lines)
+  }
+
+  #[test]
+  fn test_deleted_file() {
+    let sut = Changes::parse(DIFF);
+    assert!(!sut.contains(Path::new("bar.c"), 1))
+  }
+
+  #[test]
+  fn test_overflowing_hunk() {
+    assert_eq!(None, hunk_range("@@ -1 +4294967295,2 @@"))
+  }
+
+  #[test]
+  fn test_empty_diff() {
+    assert!(!Changes::parse("").contains(Path::new("src/foo.c"), 3))
+  }
+}
@@ -0,0 +1,231 @@
+use crate::entry::Case;
+use crate::entry::Format;
+use crate::entry::Kind;
+use crate::severity::SeverityMap;
+use crate::shape::REGEX_MAX_SIZE;
+
+/// Everything `run_with` needs to know besides the input, built step by
+/// step from the errorformat string. Options left alone behave like
+/// `run`: no static filename, strict whitespace and indentation, errors
+/// unless told otherwise, unmatched lines dropped, default severity
+/// thresholds, plain numbers, a conservative limit on the size of the
+/// compiled regex, positions of at least 1 and Kakoune's format.
+///
+/// ```
+/// let config = errfmt::Config::new("%f:%l: %m")
+///   .file("main.c")
+///   .output(errfmt::Format::Compact);
+/// let messages = errfmt::run_with("a.c:3: oops", &config).unwrap();
+/// assert_eq!(vec![String::from("main.c:3:1:E:oops")], messages);
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+  pub(crate) errfmt: String,
+  pub(crate) file: String,
+  pub(crate) loose: bool,
+  pub(crate) lenient: bool,
+  pub(crate) ignore_indent: bool,
+  pub(crate) default_kind: Kind,
+  pub(crate) notes: Option<String>,
+  pub(crate) severity: SeverityMap,
+  pub(crate) thousands: Option<char>,
+  pub(crate) size_limit: usize,
+  pub(crate) min_position: u32,
+  pub(crate) output: Format,
+  pub(crate) case: Case,
+}
+
+impl Config {
+  pub fn new(errfmt: impl AsRef<str>) -> Self {
+    Config {
+      errfmt: errfmt.as_ref().to_string(),
+      file: String::new(),
+      loose: false,
+      lenient: false,
+      ignore_indent: false,
+      default_kind: Kind::Error,
+      notes: None,
+      severity: SeverityMap::default(),
+      thousands: None,
+      size_limit: REGEX_MAX_SIZE,
+      min_position: 1,
+      output: Format::Kak,
+      case: Case::Lower,
+    }
+  }
+
+  /// Static filename replacing every path, ignored when empty.
+  pub fn file(mut self, file: impl AsRef<str>) -> Self {
+    self.file = file.as_ref().to_string();
+    self
+  }
+
+  /// Whitespace between fields (`%.`) may skip unrelated lines, as few
+  /// as possible: this copes with tools whose diagnostics get
+  /// interrupted by another stream. Whitespace closing a record is left
+  /// alone.
+  pub fn loose(mut self, loose: bool) -> Self {
+    self.loose = loose;
+    self
+  }
+
+  /// Lines that no match touches become bare entries instead of being
+  /// dropped: the whole line is the message, located on line 1 of the
+  /// static file.
+  pub fn lenient(mut self, lenient: bool) -> Self {
+    self.lenient = lenient;
+    self
+  }
+
+  /// Records may be indented by any amount of spaces and tabs, which is
+  /// not part of their first field.
+  pub fn ignore_indent(mut self, ignore: bool) -> Self {
+    self.ignore_indent = ignore;
+    self
+  }
+
+  /// Kind of the entries when the errorformat string does not read one.
+  pub fn default_kind(mut self, kind: Kind) -> Self {
+    self.default_kind = kind;
+    self
+  }
+
+  /// Secondary errorformat string for the lines the main one leaves
+  /// out, think of the note lines of clang-tidy. Its entries are notes
+  /// whatever their kind. Lenient parsing applies to what it leaves.
+  pub fn notes(mut self, errfmt: impl AsRef<str>) -> Self {
+    self.notes = Some(errfmt.as_ref().to_string());
+    self
+  }
+
+  /// Thresholds translating numeric severities (`%s`) to kinds.
+  pub fn severity(mut self, severity: SeverityMap) -> Self {
+    self.severity = severity;
+    self
+  }
+
+  /// Localized tools may group the digits of lines and columns with a
+  /// separator (`1,234` or `1.234`), which is then left out.
+  pub fn thousands_separator(mut self, separator: char) -> Self {
+    self.thousands = Some(separator);
+    self
+  }
+
+  /// Approximate size in bytes that the compiled regex may take. The
+  /// default one keeps untrusted errorformat strings from exhausting
+  /// memory, `usize::MAX` lifts it for trusted local use.
+  pub fn size_limit(mut self, limit: usize) -> Self {
+    self.size_limit = limit;
+    self
+  }
+
+  /// Lowest line and column rendered, 0 keeps what the tool reported.
+  pub fn min_position(mut self, min: u32) -> Self {
+    self.min_position = min;
+    self
+  }
+
+  /// Shape of the rendered entries, Kakoune's format by default.
+  pub fn output(mut self, format: Format) -> Self {
+    self.output = format;
+    self
+  }
+
+  /// Case of the rendered kinds, lowercase by default.
+  pub fn case(mut self, case: Case) -> Self {
+    self.case = case;
+    self
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_defaults() {
+    let sut = Config::new("%f:%l: %m");
+    assert_eq!(
+      (
+        "%f:%l: %m",
+        "",
+        false,
+        false,
+        Kind::Error,
+        None,
+        1,
+        Format::Kak,
+        Case::Lower
+      ),
+      (
+        sut.errfmt.as_str(),
+        sut.file.as_str(),
+        sut.loose,
+        sut.lenient,
+        sut.default_kind,
+        sut.notes,
+        sut.min_position,
+        sut.output,
+        sut.case
+      )
+    )
+  }
+
+  #[test]
+  fn test_builder() {
+    let sut = Config::new("%f:%l: %m")
+      .file("a.c")
+      .loose(true)
+      .lenient(true)
+      .default_kind(Kind::Warning)
+      .notes("%f:%l: note: %m")
+      .severity("error=1".parse().unwrap())
+      .min_position(0)
+      .output(Format::Jsonl)
+      .case(Case::Upper);
+    assert_eq!(
+      (
+        "a.c",
+        true,
+        true,
+        Kind::Warning,
+        Some("%f:%l: note: %m"),
+        0,
+        Format::Jsonl,
+        Case::Upper
+      ),
+      (
+        sut.file.as_str(),
+        sut.loose,
+        sut.lenient,
+        sut.default_kind,
+        sut.notes.as_deref(),
+        sut.min_position,
+        sut.output,
+        sut.case
+      )
+    );
+    assert_eq!(Kind::Error, sut.severity.kind(1))
+  }
+
+  #[test]
+  fn test_ignore_indent() {
+    assert!(!Config::new("%f:%l: %m").ignore_indent);
+    assert!(Config::new("%f:%l: %m").ignore_indent(true).ignore_indent)
+  }
+
+  #[test]
+  fn test_size_limit() {
+    assert_eq!(128 * 1024, Config::new("%f:%l: %m").size_limit);
+    assert_eq!(1, Config::new("%f:%l: %m").size_limit(1).size_limit)
+  }
+
+  #[test]
+  fn test_thousands_separator() {
+    assert_eq!(None, Config::new("%f:%l: %m").thousands);
+    assert_eq!(
+      Some('.'),
+      Config::new("%f:%l: %m").thousands_separator('.').thousands
+    )
+  }
+}
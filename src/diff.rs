@@ -0,0 +1,168 @@
+use crate::entry::Entry;
+use crate::entry::Kind;
+use regex::Regex;
+
+/// Read unified diffs (`git diff`, `diff -u`) to list what changed: each
+/// block of consecutive added or removed lines is a note located in the
+/// new version of the file, the first line of the block being the
+/// message. Added lines also give the end of the block. Deleted files
+/// are left out since there is nothing to jump to. A non-empty static
+/// filename replaces every path. Hunks are read up to the number of
+/// lines their header announces so that a removed line starting with
+/// `--` is not mistaken for the header of the next file.
+pub fn parse_diff(input: impl AsRef<str>, file: impl AsRef<str>) -> Vec<Entry> {
+  let mut entries = Vec::new();
+  let mut path: Option<String> = None;
+  let mut line = 0;
+  let (mut old, mut new) = (0, 0);
+  let mut block: Option<Entry> = None;
+  for text in input.as_ref().lines() {
+    if old == 0 && new == 0 {
+      entries.extend(block.take());
+      if let Some(header) = text.strip_prefix("+++ ") {
+        path = new_path(header);
+      } else if let Some(hunk) = hunk(text) {
+        line = hunk.0;
+        old = hunk.1;
+        new = hunk.2;
+      }
+      continue;
+    }
+    match text.chars().next() {
+      Some(sign @ '+') | Some(sign @ '-') => {
+        if let Some(path) = &path {
+          let entry = block.get_or_insert_with(|| {
+            let mut entry = Entry::new();
+            entry.file = match file.as_ref() {
+              "" => path.clone(),
+              file => file.to_string(),
+            };
+            entry.line = line;
+            entry.kind = Kind::Note;
+            entry.message = text.trim_end().to_string();
+            entry
+          });
+          if sign == '+' {
+            entry.end_line = Some(line).filter(|end| *end != entry.line);
+          }
+        }
+        if sign == '+' {
+          new = new.saturating_sub(1);
+          line += 1;
+        } else {
+          old = old.saturating_sub(1);
+        }
+      }
+      Some('\\') => (),
+      _ => {
+        entries.extend(block.take());
+        old = old.saturating_sub(1);
+        new = new.saturating_sub(1);
+        line += 1;
+      }
+    }
+  }
+  entries.extend(block);
+  entries
+}
+
+/// Git prefixes the new path with `b/`, `diff -u` may append a tab
+/// followed by a timestamp. `/dev/null` stands for a deleted file.
+fn new_path(header: &str) -> Option<String> {
+  let path = header.split('\t').next().unwrap_or(header);
+  match path.strip_prefix("b/").unwrap_or(path) {
+    "/dev/null" => None,
+    path => Some(path.to_string()),
+  }
+}
+
+/// Header of a hunk: `@@ -a,b +c,d @@` gives the first line in the new
+/// file (`c`) along with the number of old (`b`) and new (`d`) lines,
+/// 1 when omitted. An empty range starts after the given line.
+fn hunk(text: &str) -> Option<(u32, u32, u32)> {
+  lazy_static! {
+    static ref RE: Regex = Regex::new(r"^@@ -\d+(?:,(\d+))? \+(\d+)(?:,(\d+))? @@").unwrap();
+  }
+  let captures = RE.captures(text)?;
+  let number = |n: usize| {
+    captures
+      .get(n)
+      .map_or(Some(1), |value| value.as_str().parse::<u32>().ok())
+  };
+  let (old, start, new) = (number(1)?, number(2)?, number(3)?);
+  match new {
+    0 => Some((start.saturating_add(1), old, new)),
+    _ => Some((start, old, new)),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn render(input: &str) -> Vec<String> {
+    parse_diff(input, "").iter().map(Entry::to_string).collect()
+  }
+
+  #[test]
+  fn test_added_lines() {
+    let input = "+++ b/a.c\n@@ -1,2 +1,4 @@\n one\n+two\n+three\n four\n";
+    assert_eq!(vec!["a.c:2:1: warning: +two"], render(input));
+    assert_eq!(Some(3), parse_diff(input, "")[0].end_line)
+  }
+
+  #[test]
+  fn test_removed_lines_point_at_the_next_line() {
+    let input = "+++ b/a.c\n@@ -1,3 +1,2 @@\n one\n-two\n three\n";
+    assert_eq!(vec!["a.c:2:1: warning: -two"], render(input))
+  }
+
+  #[test]
+  fn test_separate_blocks() {
+    let input = "+++ b/a.c\n@@ -10,2 +10,3 @@\n-a\n+b\n c\n+d\n@@ -20 +20 @@\n-e\n+f\n";
+    assert_eq!(
+      vec![
+        "a.c:10:1: warning: -a",
+        "a.c:12:1: warning: +d",
+        "a.c:20:1: warning: -e"
+      ],
+      render(input)
+    )
+  }
+
+  #[test]
+  fn test_deleted_file_is_skipped() {
+    let input = "--- a/a.c\n+++ /dev/null\n@@ -1 +0,0 @@\n-gone\n";
+    assert!(render(input).is_empty())
+  }
+
+  #[test]
+  fn test_missing_newline_marker() {
+    let input = "+++ b/a.c\n@@ -1 +1 @@\n-a\n\\ No newline at end of file\n+b\n";
+    assert_eq!(vec!["a.c:1:1: warning: -a"], render(input))
+  }
+
+  #[test]
+  fn test_removed_line_looking_like_a_header() {
+    let input =
+      "+++ b/a.sql\n@@ -1,2 +1 @@\n--- comment\n select 1;\n+++ b/b.sql\n@@ -1 +1 @@\n-a\n+b\n";
+    assert_eq!(
+      vec!["a.sql:1:1: warning: --- comment", "b.sql:1:1: warning: -a"],
+      render(input)
+    )
+  }
+
+  #[test]
+  fn test_plain_diff_header() {
+    assert_eq!(
+      Some(String::from("a.c")),
+      new_path("a.c\t2024-01-02 03:04:05.000000000 +0100")
+    )
+  }
+
+  #[test]
+  fn test_static_filename() {
+    let input = "+++ b/a.c\n@@ -1 +1 @@\n-a\n+b\n";
+    assert_eq!("b.c", parse_diff(input, "b.c")[0].file)
+  }
+}
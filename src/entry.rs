@@ -1,4 +1,8 @@
+use std::cmp::Ordering;
 use std::fmt;
+use std::iter;
+use std::str::FromStr;
+use unicode_segmentation::UnicodeSegmentation;
 
 /// An Entry is a location (file, line) that is meant to be compatible
 /// with kak(1)'s definition.
@@ -6,9 +10,15 @@ use std::fmt;
 pub struct Entry {
   pub file: String,
   pub line: u32,
+  pub end_line: Option<u32>,
   pub column: u32,
+  pub end_column: Option<u32>,
   pub kind: Kind,
+  pub code: Option<String>,
   pub message: String,
+  pub offset: Option<u32>,
+  pub level: Option<u32>,
+  pub(crate) span: Option<(usize, usize)>,
 }
 
 impl Entry {
@@ -18,31 +28,419 @@ impl Entry {
     Entry {
       file: String::new(),
       line: 1,
+      end_line: None,
       column: 1,
+      end_column: None,
       kind: Kind::Error,
+      code: None,
       message: String::new(),
+      offset: None,
+      level: None,
+      span: None,
     }
   }
+
+  /// Byte range of the input the entry was extracted from, if any.
+  pub fn span(&self) -> Option<(usize, usize)> {
+    self.span
+  }
+
+  /// Build a complete entry at once, other fields keep their default
+  /// values.
+  ///
+  /// ```
+  /// use errfmt::{Entry, Kind};
+  /// let entry = Entry::with("/tmp/foo", 2, 3, Kind::Warning, "syntax error");
+  /// assert_eq!("/tmp/foo:2:3: warning: syntax error", entry.to_string());
+  /// ```
+  pub fn with(
+    file: impl Into<String>,
+    line: u32,
+    column: u32,
+    kind: Kind,
+    message: impl Into<String>,
+  ) -> Self {
+    Entry {
+      file: file.into(),
+      line,
+      column,
+      kind,
+      message: message.into(),
+      ..Self::new()
+    }
+  }
+
+  /// lint.kak is 1-indexed, but some tools use 0 to designate the start
+  /// of a line (or of a file).
+  pub fn clamp(mut self, min: u32) -> Self {
+    self.line = self.line.max(min);
+    self.end_line = self.end_line.map(|line| line.max(min));
+    self.column = self.column.max(min);
+    self.end_column = self.end_column.map(|column| column.max(min));
+    self
+  }
+
+  /// Some tools repeat the kind at the beginning of the message. Only a
+  /// whole word matching the rendered kind is removed, along with a
+  /// colon and whitespace that may follow.
+  pub fn trim_kind_prefix(mut self) -> Self {
+    let kind = self.kind.to_string();
+    let head = match self.message.get(..kind.len()) {
+      Some(head) if head.eq_ignore_ascii_case(&kind) => head.len(),
+      _ => return self,
+    };
+    let rest = &self.message[head..];
+    if rest.starts_with(|c: char| c.is_alphanumeric() || c == '_') {
+      return self;
+    }
+    let rest = rest.strip_prefix(':').unwrap_or(rest).trim_start();
+    self.message = rest.to_string();
+    self
+  }
+
+  /// Aligned output (think eslint's stylish format) pads messages with
+  /// runs of spaces: each run of whitespace becomes a single space.
+  pub fn squeeze_whitespace(mut self) -> Self {
+    self.message = self
+      .message
+      .split_whitespace()
+      .collect::<Vec<_>>()
+      .join(" ");
+    self
+  }
+
+  /// Line-based consumers such as lint.kak read one entry per line: line
+  /// breaks captured along with the message (think of a custom regex, or
+  /// of CRLF input) are replaced by the given marker. Trailing ones are
+  /// dropped.
+  pub fn escape_line_breaks(mut self, marker: &str) -> Self {
+    let message = self.message.trim_end_matches(['\r', '\n']);
+    self.message = message.replace("\r\n", "\n").replace(['\r', '\n'], marker);
+    self
+  }
+
+  /// Keep huge messages scannable: past the given number of characters,
+  /// the rest is replaced by an ellipsis.
+  pub fn truncate_message(mut self, max: usize) -> Self {
+    if let Some((end, _)) = self.message.char_indices().nth(max) {
+      self.message.truncate(end);
+      self.message.push('…');
+    }
+    self
+  }
+
+  /// Attribute the entry to the tool that reported it. The tag goes
+  /// inside the message so that lint.kak still reads every field.
+  /// Messages already carrying the same tag are left alone so that
+  /// output fed back through errfmt is not tagged twice.
+  pub fn tag(mut self, name: &str) -> Self {
+    let tag = format!("[{}] ", name);
+    if !self.message.starts_with(&tag) {
+      self.message = tag + &self.message;
+    }
+    self
+  }
+
+  /// Kakoune expects byte columns, some tools count characters instead.
+  /// The reported columns are translated against the source line when
+  /// the file is available. Out of range columns are left untouched.
+  pub fn convert_columns(mut self, unit: ColumnUnit, source: Option<&str>) -> Self {
+    if unit == ColumnUnit::Byte || self.line == 0 {
+      return self;
+    }
+    let line = match source.and_then(|source| source.lines().nth(self.line as usize - 1)) {
+      Some(line) => line,
+      None => return self,
+    };
+    self.column = unit.to_byte_column(line, self.column);
+    self.end_column = self
+      .end_column
+      .map(|column| unit.to_byte_column(line, column));
+    self
+  }
+
+  /// Stable key telling entries apart across runs: a hash of the file,
+  /// the position and the message (along with the code, as rendered).
+  /// The kind does not count. FNV-1a gives the same value whatever the
+  /// platform or the version of Rust, unlike the standard hasher.
+  ///
+  /// ```
+  /// let entry = errfmt::Entry::with("a.c", 1, 2, errfmt::Kind::Error, "foo");
+  /// let again = errfmt::parse(entry.to_string(), errfmt::PASSTHROUGH_ERRFMT, "").unwrap();
+  /// assert_eq!(entry.id(), again[0].id());
+  /// ```
+  pub fn id(&self) -> u64 {
+    const OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    [
+      self.file.as_bytes(),
+      &[0],
+      &self.line.to_le_bytes(),
+      &self.column.to_le_bytes(),
+      self.full_message().as_bytes(),
+    ]
+    .concat()
+    .iter()
+    .fold(OFFSET, |hash, byte| {
+      (hash ^ *byte as u64).wrapping_mul(PRIME)
+    })
+  }
+
+  /// The code, if any, is kept along with the message so that it is
+  /// not lost in text outputs.
+  fn full_message(&self) -> String {
+    match &self.code {
+      Some(code) => format!("{} [{}]", self.message, code),
+      None => self.message.clone(),
+    }
+  }
+
+  /// Line number, or range of lines when it spans several of them.
+  fn lines(&self) -> String {
+    match self.end_line {
+      Some(end) if end != self.line => format!("{}-{}", self.line, end),
+      _ => self.line.to_string(),
+    }
+  }
+
+  /// Some tools only report a byte offset from the beginning of the
+  /// file. When the file is available, the offset is translated to a
+  /// location. Otherwise, it is kept in the message.
+  pub fn locate(mut self, source: Option<&str>) -> Self {
+    let offset = match self.offset.take() {
+      Some(offset) => offset as usize,
+      None => return self,
+    };
+    match source.and_then(|source| source.get(..offset)) {
+      Some(before) => {
+        let start = before.rfind('\n').map_or(0, |n| n + 1);
+        self.line = before.matches('\n').count() as u32 + 1;
+        self.column = (offset - start) as u32 + 1;
+      }
+      None => self.message = format!("{} (offset {})", self.message, offset),
+    }
+    self
+  }
+
+  /// Render the entry according to the given output format. The case
+  /// only applies to spelled out kinds. lint.kak has no notion of
+  /// range: the end line and column only show up in JSON and in the
+  /// range variant, where a missing end is the start itself.
+  pub fn render(&self, format: Format, case: Case) -> String {
+    match format {
+      Format::KakRange => format!(
+        "{}:{}.{},{}.{}: {}: {}",
+        self.file,
+        self.line,
+        self.column,
+        self.end_line.unwrap_or(self.line),
+        self.end_column.unwrap_or(self.column),
+        self.kind.render(case),
+        self.full_message()
+      ),
+      Format::Kak => format!(
+        "{}:{}:{}: {}: {}",
+        self.file,
+        self.line,
+        self.column,
+        self.kind.render(case),
+        self.full_message()
+      ),
+      Format::Compact => format!(
+        "{}:{}:{}:{}:{}",
+        self.file,
+        self.line,
+        self.column,
+        self.kind.letter(),
+        self.full_message()
+      ),
+      Format::Grep => format!(
+        "{}:{}:{}:{}",
+        self.file,
+        self.line,
+        self.column,
+        self.full_message()
+      ),
+      Format::Jsonl => format!(
+        r#"{{"file":{},"line":{},"end_line":{},"column":{},"end_column":{},"kind":{},"code":{},"message":{}}}"#,
+        json_string(&self.file),
+        self.line,
+        self
+          .end_line
+          .map_or(String::from("null"), |line| line.to_string()),
+        self.column,
+        self
+          .end_column
+          .map_or(String::from("null"), |column| column.to_string()),
+        json_string(&self.kind.render(case)),
+        self
+          .code
+          .as_deref()
+          .map_or(String::from("null"), json_string),
+        json_string(&self.message)
+      ),
+    }
+  }
+}
+
+/// Human-readable rendering: each file is printed once, in order of
+/// first appearance, followed by its indented entries. Line ranges are
+/// spelled out.
+pub fn render_grouped(entries: &[Entry], case: Case) -> String {
+  let mut files: Vec<(&str, Vec<&Entry>)> = Vec::new();
+  for entry in entries {
+    match files.iter_mut().find(|(file, _)| *file == entry.file) {
+      Some((_, group)) => group.push(entry),
+      None => files.push((&entry.file, vec![entry])),
+    }
+  }
+  files
+    .iter()
+    .flat_map(|(file, group)| {
+      iter::once(file.to_string()).chain(group.iter().map(move |entry| {
+        format!(
+          "  {}:{}: {}: {}",
+          entry.lines(),
+          entry.column,
+          entry.kind.render(case),
+          entry.full_message()
+        )
+      }))
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Notes attached to a diagnostic are appended to its message instead
+/// of standing on their own. Notes without a preceding diagnostic are
+/// kept as is.
+pub fn fold_notes(entries: Vec<Entry>) -> Vec<Entry> {
+  entries.into_iter().fold(Vec::new(), |mut acc, entry| {
+    match acc.last_mut() {
+      Some(last) if entry.kind == Kind::Note && last.kind != Kind::Note => {
+        last.message = format!("{}; {}: {}", last.message, NOTE, entry.message)
+      }
+      _ => acc.push(entry),
+    };
+    acc
+  })
+}
+
+/// Quote and escape a string as per RFC 8259. This is all the JSON
+/// needed here, hence no dedicated dependency.
+fn json_string(value: &str) -> String {
+  let mut result = String::with_capacity(value.len() + 2);
+  result.push('"');
+  for c in value.chars() {
+    match c {
+      '"' => result.push_str(r#"\""#),
+      '\\' => result.push_str(r"\\"),
+      '\n' => result.push_str(r"\n"),
+      '\r' => result.push_str(r"\r"),
+      '\t' => result.push_str(r"\t"),
+      c if (c as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", c as u32)),
+      c => result.push(c),
+    }
+  }
+  result.push('"');
+  result
+}
+
+/// Two entries are equal when they point to the same diagnostic: where
+/// they were found in the input does not matter.
+impl PartialEq for Entry {
+  fn eq(&self, other: &Self) -> bool {
+    self.file == other.file
+      && self.line == other.line
+      && self.end_line == other.end_line
+      && self.column == other.column
+      && self.end_column == other.end_column
+      && self.kind == other.kind
+      && self.code == other.code
+      && self.message == other.message
+      && self.offset == other.offset
+      && self.level == other.level
+  }
+}
+
+impl Default for Entry {
+  fn default() -> Self {
+    Self::new()
+  }
 }
 
 /// Must match kakoune's expected format. See lint.kak from standard rc
-/// scripts. One day, this will maybe support other output formats...
+/// scripts. Other output formats are available through `render`.
 impl fmt::Display for Entry {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-    write!(
-      f,
-      "{}:{}:{}: {}: {}",
-      self.file, self.line, self.column, self.kind, self.message
-    )
+    write!(f, "{}", self.render(Format::Kak, Case::Lower))
+  }
+}
+
+/// The different shapes an entry can take once rendered. Kakoune's
+/// format stays the default one. The range variant locates entries with
+/// Kakoune's selection description syntax (`line.column,line.column`,
+/// both ends included) as understood by `select` and range-specs: stock
+/// lint.kak (up to Kakoune v2024.05.18) only reads a single position,
+/// this is meant for wrappers that highlight whole spans. The grep
+/// variant is what grep.kak reads (`file:line:column:message`, as
+/// printed by `rg --vimgrep`): it has no room for a kind. JSON Lines
+/// holds one complete object per entry so that consumers can process
+/// them one by one.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+  Kak,
+  KakRange,
+  Compact,
+  Grep,
+  Jsonl,
+}
+
+/// Capitalization of the kind once rendered. lint.kak expects lower
+/// case, other consumers may be pickier.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Case {
+  Lower,
+  Upper,
+  Title,
+}
+
+/// What a column number counts from the beginning of the line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColumnUnit {
+  Byte,
+  Codepoint,
+  Grapheme,
+}
+
+impl ColumnUnit {
+  /// Columns are 1-indexed, the end of the line is a valid position.
+  fn to_byte_column(self, line: &str, column: u32) -> u32 {
+    let starts: Box<dyn Iterator<Item = usize>> = match self {
+      ColumnUnit::Byte => return column,
+      ColumnUnit::Codepoint => Box::new(line.char_indices().map(|(n, _)| n)),
+      ColumnUnit::Grapheme => Box::new(line.grapheme_indices(true).map(|(n, _)| n)),
+    };
+    match column.checked_sub(1) {
+      Some(n) => starts
+        .chain(iter::once(line.len()))
+        .nth(n as usize)
+        .map_or(column, |start| start as u32 + 1),
+      None => column,
+    }
   }
 }
 
 /// Simple representation of the error's log-level. The possible variants
-/// are purposedly limited here: lint.kak script only supports these two.
-#[derive(Debug)]
+/// are purposedly limited here: lint.kak script only supports two of
+/// them, notes and fatal errors are only distinguished when comparing
+/// severities or folding notes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Kind {
+  Note,
   Warning,
   Error,
+  Fatal,
 }
 
 /// Explicitely add encountered notations here instead of blindly accept
@@ -50,32 +448,137 @@ pub enum Kind {
 const WARNING: &str = "warning";
 const ERROR: &str = "error";
 const NOTE: &str = "note";
+const FATAL: &str = "fatal";
+const PANIC: &str = "panic";
+const INTERNAL: &str = "internal";
+
+/// Every word a kind can be read from, whatever its case.
+pub(crate) const KIND_WORDS: &[&str] = &[WARNING, ERROR, NOTE, FATAL, PANIC, INTERNAL];
+
+/// Severities spelled in several words, read as a unit. The longest
+/// ones come first since they are tried in order.
+pub(crate) const KIND_PHRASES: &[(&str, Kind)] = &[
+  ("internal compiler error", Kind::Fatal),
+  ("internal error", Kind::Fatal),
+  ("fatal error", Kind::Fatal),
+  ("parse error", Kind::Error),
+];
+
+/// Symbols decorating the lines of some tools (think of eslint's
+/// stylish formatter), possibly followed by an emoji variation selector.
+pub(crate) const KIND_SYMBOLS: &[(&str, Kind)] = &[
+  ("✖", Kind::Error),
+  ("✗", Kind::Error),
+  ("✘", Kind::Error),
+  ("❌", Kind::Error),
+  ("⚠", Kind::Warning),
+  ("ℹ", Kind::Note),
+];
 
 impl Kind {
   /// Must accept capitalized words to handle various linter
-  /// formats.
-  pub fn from(value: &str) -> Self {
-    match value.to_lowercase().as_str() {
-      WARNING | NOTE => Kind::Warning,
-      ERROR => Kind::Error,
-      value => panic!("unexpected kind: {}", value),
+  /// formats. Comparison is done in place as this runs for every
+  /// single entry. A custom regex may capture anything: unknown kinds
+  /// are `None`.
+  pub(crate) fn read(value: &str) -> Option<Self> {
+    let is = |word: &str| value.eq_ignore_ascii_case(word);
+    let symbol = value.trim_end_matches('\u{fe0f}');
+    if let Some((_, kind)) = KIND_SYMBOLS.iter().find(|(s, _)| *s == symbol) {
+      return Some(*kind);
+    }
+    if let Some((_, kind)) = KIND_PHRASES.iter().find(|(phrase, _)| is(phrase)) {
+      return Some(*kind);
+    }
+    match value {
+      _ if is(NOTE) => Some(Kind::Note),
+      _ if is(WARNING) => Some(Kind::Warning),
+      _ if is(ERROR) => Some(Kind::Error),
+      _ if is(FATAL) || is(PANIC) || is(INTERNAL) => Some(Kind::Fatal),
+      _ => None,
+    }
+  }
+
+  /// Internal ordering of kinds, the higher the more severe.
+  fn severity(self) -> u8 {
+    match self {
+      Kind::Note => 0,
+      Kind::Warning => 1,
+      Kind::Error => 2,
+      Kind::Fatal => 3,
+    }
+  }
+
+  /// Spell out the kind with the given capitalization.
+  pub fn render(self, case: Case) -> String {
+    let word = self.to_string();
+    match case {
+      Case::Lower => word,
+      Case::Upper => word.to_uppercase(),
+      Case::Title => word[..1].to_uppercase() + &word[1..],
+    }
+  }
+
+  /// Kakoune face matching the severity, as used by diagnostics
+  /// highlighters.
+  pub fn face(self) -> &'static str {
+    match self {
+      Kind::Note => "DiagnosticInfo",
+      Kind::Warning => "DiagnosticWarning",
+      Kind::Error | Kind::Fatal => "DiagnosticError",
+    }
+  }
+
+  /// Single uppercase letter for dense outputs.
+  pub fn letter(self) -> char {
+    match self {
+      Kind::Note | Kind::Warning => 'W',
+      Kind::Error | Kind::Fatal => 'E',
     }
   }
 }
 
+/// Words of `KIND_WORDS`, phrases of `KIND_PHRASES` and symbols of
+/// `KIND_SYMBOLS`, whatever their case.
+impl FromStr for Kind {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    Self::read(value).ok_or_else(|| format!("unknown kind: {}", value))
+  }
+}
+
 impl fmt::Display for Kind {
   fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
     match self {
-      Kind::Warning => write!(f, "{}", WARNING),
-      Kind::Error => write!(f, "{}", ERROR),
+      Kind::Note | Kind::Warning => write!(f, "{}", WARNING),
+      Kind::Error | Kind::Fatal => write!(f, "{}", ERROR),
     }
   }
 }
 
+impl Ord for Kind {
+  fn cmp(&self, other: &Self) -> Ordering {
+    self.severity().cmp(&other.severity())
+  }
+}
+
+impl PartialOrd for Kind {
+  fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+    Some(self.cmp(other))
+  }
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  fn kind(value: &str) -> Kind {
+    value.parse().unwrap()
+  }
+
+  /// Id of `a.c:1:2: foo`, whatever the run.
+  const ID: u64 = 0x4101_2294_8cf1_050e;
+
   #[test]
   fn test_default_entry_values() {
     let expected = String::from(":1:1: error: ");
@@ -96,31 +599,416 @@ mod tests {
     assert_eq!(expected, actual)
   }
 
+  #[test]
+  fn test_kak_range_format() {
+    let mut sut = Entry::with("/tmp/foo", 2, 3, Kind::Warning, "syntax error");
+    sut.end_column = Some(5);
+    assert_eq!(
+      "/tmp/foo:2.3,2.5: warning: syntax error",
+      sut.render(Format::KakRange, Case::Lower)
+    );
+    sut.end_line = Some(4);
+    assert_eq!(
+      "/tmp/foo:2.3,4.5: warning: syntax error",
+      sut.render(Format::KakRange, Case::Lower)
+    )
+  }
+
+  #[test]
+  fn test_kak_range_format_without_end() {
+    let sut = Entry::with("/tmp/foo", 2, 3, Kind::Error, "syntax error");
+    assert_eq!(
+      "/tmp/foo:2.3,2.3: error: syntax error",
+      sut.render(Format::KakRange, Case::Lower)
+    )
+  }
+
+  #[test]
+  fn test_compact_format() {
+    let expected = String::from("/tmp/foo:2:3:W:syntax error");
+    let sut = Entry::with("/tmp/foo", 2, 3, Kind::Warning, "syntax error");
+    let actual = sut.render(Format::Compact, Case::Lower);
+    assert_eq!(expected, actual)
+  }
+
+  #[test]
+  fn test_grep_format() {
+    let expected = String::from("/tmp/foo:2:3:syntax error [E1]");
+    let mut sut = Entry::with("/tmp/foo", 2, 3, Kind::Warning, "syntax error");
+    sut.code = Some(String::from("E1"));
+    let actual = sut.render(Format::Grep, Case::Upper);
+    assert_eq!(expected, actual)
+  }
+
+  #[test]
+  fn test_jsonl_format() {
+    let expected = r#"{"file":"/tmp/foo","line":2,"end_line":null,"column":3,"end_column":null,"kind":"warning","code":null,"message":"unexpected \"}\"\tin\\n"}"#;
+    let mut sut = Entry::new();
+    sut.file = String::from("/tmp/foo");
+    sut.line = 2;
+    sut.column = 3;
+    sut.kind = Kind::Warning;
+    sut.message = String::from("unexpected \"}\"\tin\\n");
+    let actual = sut.render(Format::Jsonl, Case::Lower);
+    assert_eq!(expected, actual)
+  }
+
+  #[test]
+  fn test_jsonl_end_column() {
+    let mut sut = Entry::new();
+    sut.end_column = Some(9);
+    let actual = sut.render(Format::Jsonl, Case::Lower);
+    assert!(actual.contains(r#""column":1,"end_column":9,"#))
+  }
+
+  #[test]
+  fn test_json_control_characters_are_escaped() {
+    assert_eq!(r#""\u001b[0m""#, json_string("\x1b[0m"))
+  }
+
+  #[test]
+  fn test_code_is_rendered_after_the_message() {
+    let mut sut = Entry::new();
+    sut.code = Some(String::from("TS2322"));
+    sut.message = String::from("type mismatch");
+    assert_eq!(":1:1: error: type mismatch [TS2322]", sut.to_string());
+    assert!(sut
+      .render(Format::Jsonl, Case::Lower)
+      .contains(r#""code":"TS2322","message":"type mismatch""#))
+  }
+
+  #[test]
+  fn test_kak_format_is_the_default_display() {
+    let sut = Entry::new();
+    assert_eq!(sut.to_string(), sut.render(Format::Kak, Case::Lower))
+  }
+
+  #[test]
+  fn test_upper_case_kind() {
+    let expected = String::from(":1:1: ERROR: ");
+    let actual = Entry::new().render(Format::Kak, Case::Upper);
+    assert_eq!(expected, actual)
+  }
+
+  #[test]
+  fn test_faces() {
+    assert_eq!("DiagnosticInfo", Kind::Note.face());
+    assert_eq!("DiagnosticError", Kind::Fatal.face())
+  }
+
+  #[test]
+  fn test_title_case_kind() {
+    assert_eq!("Warning", Kind::Warning.render(Case::Title))
+  }
+
+  #[test]
+  fn test_clamp_positions() {
+    let mut sut = Entry::new();
+    sut.line = 0;
+    sut.column = 0;
+    let actual = sut.clamp(1);
+    assert_eq!((1, 1), (actual.line, actual.column))
+  }
+
+  #[test]
+  fn test_trim_kind_prefix() {
+    let tests = vec![
+      ("error: foo", Kind::Error, "foo"),
+      ("Error foo", Kind::Error, "foo"),
+      ("ERROR:foo", Kind::Fatal, "foo"),
+      ("errors: foo", Kind::Error, "errors: foo"),
+      ("error: foo", Kind::Warning, "error: foo"),
+      ("warning", Kind::Warning, ""),
+      ("é", Kind::Error, "é"),
+    ];
+    for (message, kind, expected) in tests {
+      let mut sut = Entry::new();
+      sut.message = String::from(message);
+      sut.kind = kind;
+      assert_eq!(expected, sut.trim_kind_prefix().message);
+    }
+  }
+
+  #[test]
+  fn test_squeeze_whitespace() {
+    let tests = vec![
+      ("foo  bar", "foo bar"),
+      ("foo \t\n bar   baz", "foo bar baz"),
+      ("  foo  ", "foo"),
+      ("foo", "foo"),
+    ];
+    for (message, expected) in tests {
+      let mut sut = Entry::new();
+      sut.message = String::from(message);
+      assert_eq!(expected, sut.squeeze_whitespace().message);
+    }
+  }
+
+  #[test]
+  fn test_truncate_message() {
+    let tests = vec![
+      ("foobar", 3, "foo…"),
+      ("foo", 3, "foo"),
+      ("éàü", 2, "éà…"),
+      ("foo", 0, "…"),
+    ];
+    for (message, max, expected) in tests {
+      let mut sut = Entry::new();
+      sut.message = String::from(message);
+      assert_eq!(expected, sut.truncate_message(max).message);
+    }
+  }
+
+  #[test]
+  fn test_tag() {
+    let mut sut = Entry::new();
+    sut.file = String::from("a.c");
+    sut.message = String::from("foo");
+    assert_eq!("a.c:1:1: error: [gcc] foo", sut.tag("gcc").to_string())
+  }
+
+  #[test]
+  fn test_id_is_stable() {
+    let sut = Entry::with("a.c", 1, 2, Kind::Error, "foo");
+    assert_eq!(ID, sut.id())
+  }
+
+  #[test]
+  fn test_id_ignores_the_kind() {
+    let sut = Entry::with("a.c", 1, 2, Kind::Note, "foo");
+    assert_eq!(ID, sut.id())
+  }
+
+  #[test]
+  fn test_id_tells_entries_apart() {
+    let tests = vec![
+      Entry::with("b.c", 1, 2, Kind::Error, "foo"),
+      Entry::with("a.c", 2, 2, Kind::Error, "foo"),
+      Entry::with("a.c", 1, 3, Kind::Error, "foo"),
+      Entry::with("a.c", 1, 2, Kind::Error, "bar"),
+      Entry::with("a.c", 258, 0, Kind::Error, "foo"),
+    ];
+    for sut in tests {
+      assert_ne!(ID, sut.id(), "{}", sut);
+    }
+  }
+
+  #[test]
+  fn test_id_includes_the_code() {
+    let mut sut = Entry::with("a.c", 1, 2, Kind::Error, "foo");
+    sut.code = Some(String::from("E1"));
+    assert_ne!(ID, sut.id());
+    assert_eq!(
+      Entry::with("a.c", 1, 2, Kind::Error, "foo [E1]").id(),
+      sut.id()
+    )
+  }
+
+  #[test]
+  fn test_escape_line_breaks() {
+    let tests = vec![
+      ("foo", "foo"),
+      ("foo\r", "foo"),
+      ("foo\nbar\n", "foo⏎bar"),
+      ("foo\r\nbar\rbaz", "foo⏎bar⏎baz"),
+    ];
+    for (message, expected) in tests {
+      let mut sut = Entry::new();
+      sut.message = String::from(message);
+      assert_eq!(expected, sut.escape_line_breaks("⏎").message);
+    }
+  }
+
+  #[test]
+  fn test_tag_twice() {
+    let mut sut = Entry::new();
+    sut.message = String::from("foo");
+    assert_eq!(
+      "[clang] [gcc] foo",
+      sut.tag("gcc").tag("gcc").tag("clang").message
+    )
+  }
+
+  #[test]
+  fn test_codepoint_columns() {
+    let mut sut = Entry::new();
+    sut.line = 2;
+    sut.column = 3;
+    sut.end_column = Some(4);
+    let actual = sut.convert_columns(ColumnUnit::Codepoint, Some("\nété\n"));
+    assert_eq!((4, Some(6)), (actual.column, actual.end_column))
+  }
+
+  #[test]
+  fn test_grapheme_columns() {
+    let mut sut = Entry::new();
+    sut.column = 2;
+    let actual = sut.convert_columns(ColumnUnit::Grapheme, Some("e\u{301}x"));
+    assert_eq!(4, actual.column)
+  }
+
+  #[test]
+  fn test_columns_without_source() {
+    let mut sut = Entry::new();
+    sut.column = 2;
+    let actual = sut.convert_columns(ColumnUnit::Codepoint, None);
+    assert_eq!(2, actual.column)
+  }
+
+  #[test]
+  fn test_out_of_range_columns() {
+    let mut sut = Entry::new();
+    sut.column = 42;
+    let actual = sut.convert_columns(ColumnUnit::Codepoint, Some("é"));
+    assert_eq!(42, actual.column)
+  }
+
+  #[test]
+  fn test_locate_offset() {
+    let mut sut = Entry::new();
+    sut.offset = Some(9);
+    let actual = sut.locate(Some("{\n  \"a\": ,\n}"));
+    assert_eq!((2, 8), (actual.line, actual.column))
+  }
+
+  #[test]
+  fn test_locate_offset_without_source() {
+    let mut sut = Entry::new();
+    sut.offset = Some(9);
+    sut.message = String::from("unexpected token");
+    let actual = sut.locate(None);
+    assert_eq!(
+      ":1:1: error: unexpected token (offset 9)",
+      actual.to_string()
+    )
+  }
+
+  #[test]
+  fn test_locate_offset_out_of_source() {
+    let mut sut = Entry::new();
+    sut.offset = Some(42);
+    let actual = sut.locate(Some("{}"));
+    assert_eq!(":1:1: error:  (offset 42)", actual.to_string())
+  }
+
   #[test]
   fn test_error_kind() {
     let expected = Kind::Error.to_string();
-    let actual = Kind::from("error").to_string();
+    let actual = kind("error").to_string();
     assert_eq!(expected, actual)
   }
 
   #[test]
   fn test_warning_kind() {
     let expected = Kind::Warning.to_string();
-    let actual = Kind::from("warning").to_string();
+    let actual = kind("warning").to_string();
     assert_eq!(expected, actual)
   }
 
   #[test]
   fn test_note_kind() {
     let expected = Kind::Warning.to_string();
-    let actual = Kind::from("note").to_string();
+    let actual = kind("note").to_string();
     assert_eq!(expected, actual)
   }
 
+  #[test]
+  fn test_fatal_kind_renders_as_error() {
+    let expected = Kind::Error.to_string();
+    let actual = kind("panic").to_string();
+    assert_eq!(expected, actual)
+  }
+
+  #[test]
+  fn test_render_grouped() {
+    let entry = |file: &str, line| {
+      let mut entry = Entry::new();
+      entry.file = String::from(file);
+      entry.line = line;
+      entry.message = String::from("foo");
+      entry
+    };
+    let mut range = entry("b.c", 3);
+    range.end_line = Some(5);
+    let entries = vec![entry("b.c", 2), entry("a.c", 1), range];
+    assert_eq!(
+      "b.c\n  2:1: error: foo\n  3-5:1: error: foo\na.c\n  1:1: error: foo",
+      render_grouped(&entries, Case::Lower)
+    )
+  }
+
+  #[test]
+  fn test_render_grouped_with_code() {
+    let mut entry = Entry::new();
+    entry.file = String::from("a.ts");
+    entry.message = String::from("foo");
+    entry.code = Some(String::from("TS2322"));
+    assert_eq!(
+      "a.ts\n  1:1: error: foo [TS2322]",
+      render_grouped(&[entry], Case::Lower)
+    )
+  }
+
+  #[test]
+  fn test_fold_notes() {
+    let entry = |kind, message: &str| {
+      let mut entry = Entry::new();
+      entry.kind = kind;
+      entry.message = String::from(message);
+      entry
+    };
+    let actual = fold_notes(vec![
+      entry(Kind::Note, "orphan"),
+      entry(Kind::Error, "foo"),
+      entry(Kind::Note, "bar"),
+      entry(Kind::Note, "baz"),
+      entry(Kind::Warning, "qux"),
+    ]);
+    let messages: Vec<_> = actual.iter().map(|entry| entry.message.as_str()).collect();
+    assert_eq!(vec!["orphan", "foo; note: bar; note: baz", "qux"], messages)
+  }
+
+  #[test]
+  fn test_kinds_are_ordered_by_severity() {
+    assert!(Kind::Note < Kind::Warning);
+    assert!(Kind::Warning < Kind::Error);
+    assert!(Kind::Error < Kind::Fatal);
+    assert!(kind("internal") > kind("error"))
+  }
+
+  #[test]
+  fn test_kind_from_phrase() {
+    assert_eq!(
+      vec![Kind::Fatal, Kind::Fatal, Kind::Fatal, Kind::Error],
+      vec![
+        kind("fatal error"),
+        kind("Internal Error"),
+        kind("internal compiler error"),
+        kind("Parse error"),
+      ]
+    )
+  }
+
+  #[test]
+  fn test_kind_from_symbol() {
+    assert_eq!(
+      (Kind::Error, Kind::Warning, Kind::Warning, Kind::Note),
+      (kind("✖"), kind("⚠"), kind("⚠\u{fe0f}"), kind("ℹ"))
+    )
+  }
+
+  #[test]
+  fn test_unknown_kind() {
+    assert_eq!(
+      Err(String::from("unknown kind: bogus")),
+      "bogus".parse::<Kind>()
+    )
+  }
+
   #[test]
   fn test_word_can_be_capitalized() {
     let expected = Kind::Error.to_string();
-    let actual = Kind::from("Error").to_string();
+    let actual = kind("Error").to_string();
     assert_eq!(expected, actual)
   }
 }
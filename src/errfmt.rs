@@ -2,16 +2,45 @@ use regex::Regex;
 
 pub const ESLINT_ERRFMT: &str = r"%.%f%.%l:%c  %k  %m";
 pub const GOLINT_ERRFMT: &str = r"%f:%l:%c: %m";
+pub const GREP_ERRFMT: &str = r"%f:%l:%m";
 pub const PASSTHROUGH_ERRFMT: &str = r"%f:%l:%c: %k: %m";
 pub const PHP_ERRFMT: &str = r"%k: %m in %f on line %l";
+pub const RIPGREP_ERRFMT: &str = r"%f:%l:%c:%m";
+pub const RUSTC_ERRFMT: &str = r"%k[%e]: %m%.--> %f:%l:%c";
 pub const RUSTFMT_ERRFMT: &str = r"%k%*: %m%.--> %f:%l:%c";
 pub const SHELLCHECK_ERRFMT: &str = r"%f:%l:%c: %k: %m";
+pub const TSC_ERRFMT: &str = r"%f:%l:%c - %k %e: %m";
+
+/// Registry of built-in errorformat strings, by name.
+pub fn presets() -> &'static [(&'static str, &'static str)] {
+  &[
+    ("eslint", ESLINT_ERRFMT),
+    ("golint", GOLINT_ERRFMT),
+    ("grep", GREP_ERRFMT),
+    ("passthrough", PASSTHROUGH_ERRFMT),
+    ("php", PHP_ERRFMT),
+    ("ripgrep", RIPGREP_ERRFMT),
+    ("rustc", RUSTC_ERRFMT),
+    ("rustfmt", RUSTFMT_ERRFMT),
+    ("shellcheck", SHELLCHECK_ERRFMT),
+    ("tsc", TSC_ERRFMT),
+  ]
+}
+
+/// Look up a built-in errorformat string.
+pub fn preset(name: &str) -> Option<&'static str> {
+  presets()
+    .iter()
+    .find(|(n, _)| *n == name)
+    .map(|(_, errfmt)| *errfmt)
+}
 
 /// Stream characters of the errorformat string and build logical sections
 /// (tokens) from them.
 pub fn tokenize(errfmt: String) -> Vec<String> {
-  errfmt.chars().fold(Vec::new(), |mut acc, c| {
-    if token_start(&acc, c) {
+  let errfmt = unescape(&errfmt);
+  errfmt.char_indices().fold(Vec::new(), |mut acc, (n, c)| {
+    if token_start(&acc, &errfmt[n..]) {
       let mut new = String::new();
       new.push(c);
       acc.push(new);
@@ -22,29 +51,109 @@ pub fn tokenize(errfmt: String) -> Vec<String> {
   })
 }
 
+/// Read backslash escapes printf-style: `\%` is a literal percent sign,
+/// just like `%%`, and `\\` a literal backslash. Any other backslash is
+/// kept as is, and so are percent sequences and custom regexes, which
+/// have escapes of their own.
+fn unescape(errfmt: &str) -> String {
+  let mut unescaped = String::with_capacity(errfmt.len());
+  let mut rest = errfmt;
+  while let Some(c) = rest.chars().next() {
+    let next = rest[c.len_utf8()..].chars().next();
+    let len = c.len_utf8() + next.map_or(0, char::len_utf8);
+    let len = match (c, next) {
+      ('\\', Some('%')) => {
+        unescaped.push_str("%%");
+        rest = &rest[len..];
+        continue;
+      }
+      ('\\', Some('\\')) => {
+        unescaped.push('\\');
+        rest = &rest[len..];
+        continue;
+      }
+      ('%', Some(_)) if is_overridable(&rest[..len]) => match rest[len..].strip_prefix("(/") {
+        Some(regex) => len + regex.find("/)").map_or(regex.len() + 2, |n| n + 4),
+        None => len,
+      },
+      ('%', Some(_)) => len,
+      _ => c.len_utf8(),
+    };
+    unescaped.push_str(&rest[..len]);
+    rest = &rest[len..];
+  }
+  unescaped
+}
+
 /// Guess if a given character must be added to the previous ongoing
-/// token, or if it should be the first character of a new token.
-fn token_start(acc: &[String], c: char) -> bool {
-  match (acc.len(), c, acc.last()) {
+/// token, or if it should be the first character of a new token. Runs
+/// of percent signs are read pair by pair: each `%%` is a literal one
+/// and a remaining single `%` starts a placeholder. An alternation is
+/// read up to its closing parenthesis. A data placeholder directly
+/// followed by `(/` carries its own regex, read up to the first `/)`.
+/// Whitespace directly followed by a number in braces is counted.
+fn token_start(acc: &[String], rest: &str) -> bool {
+  lazy_static! {
+    static ref COUNT: Regex = Regex::new(r"^\{[0-9]+\}").unwrap();
+  }
+  match (acc.len(), rest.chars().next(), acc.last()) {
     (0, _, _) => true,
-    (_, '%', Some(last)) => last != "%",
+    (_, _, Some(last)) if last.starts_with("%(") && !last.ends_with(')') => false,
+    (_, _, Some(last)) if last.starts_with("%.{") && !last.ends_with('}') => false,
+    (_, _, Some(last)) if last == "%." && COUNT.is_match(rest) => false,
+    (_, _, Some(last)) if is_open_override(last) => false,
+    (_, _, Some(last)) if is_overridable(last) && rest.starts_with("(/") => false,
+    (_, Some('%'), Some(last)) => last != "%",
     (_, _, Some(last)) => is_known_placeholder(last),
     _ => false,
   }
 }
 
 /// A "known" placeholder is a percent-sequence like: %f, %m, %%... etc.
+/// It is exactly two characters long, so whatever follows starts a new
+/// token. A closed alternation such as `%(a|b)` is one too, and so are
+/// a data placeholder along with its custom regex: `%f(/\S+\.rs/)` and
+/// counted whitespace: `%.{2}`.
 fn is_known_placeholder(val: &str) -> bool {
   lazy_static! {
-    static ref RE: Regex = Regex::new(r"^%[%flckm.*]$").unwrap();
+    static ref RE: Regex = Regex::new(
+      r"^(?:%[%fqlLcCksemMoTPt.~*+$]|%\([^)]*\)|%[fqlLcCksemMo]\(/.+/\)|%\.\{[0-9]+\})$"
+    )
+    .unwrap();
   }
   RE.is_match(val)
 }
 
+/// Placeholders holding data may have their regex overridden.
+fn is_overridable(val: &str) -> bool {
+  lazy_static! {
+    static ref RE: Regex = Regex::new(r"^%[fqlLcCksemMo]$").unwrap();
+  }
+  RE.is_match(val)
+}
+
+/// The custom regex of a placeholder is still being read.
+fn is_open_override(val: &str) -> bool {
+  val.len() > 2
+    && is_overridable(&val[..2])
+    && val[2..].starts_with("(/")
+    && (val.len() < 6 || !val.ends_with("/)"))
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
 
+  #[test]
+  fn test_known_preset() {
+    assert_eq!(Some(PHP_ERRFMT), preset("php"))
+  }
+
+  #[test]
+  fn test_unknown_preset() {
+    assert_eq!(None, preset("foo"))
+  }
+
   #[test]
   fn test_single_character() {
     let input = String::from("a");
@@ -74,6 +183,34 @@ mod tests {
     assert_eq!(expected, actual);
   }
 
+  #[test]
+  fn test_alternation_is_a_placeholder() {
+    let input = String::from("%(error|fatal error%%): %m");
+    let expected = vec![
+      String::from("%(error|fatal error%%)"),
+      String::from(": "),
+      String::from("%m"),
+    ];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_multiline_wildcard_is_a_placeholder() {
+    let input = String::from("%m%+%f");
+    let expected = vec![String::from("%m"), String::from("%+"), String::from("%f")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_separator_is_a_placeholder() {
+    let input = String::from("%m%$%f");
+    let expected = vec![String::from("%m"), String::from("%$"), String::from("%f")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
   #[test]
   fn test_handling_of_literal_percent_sign() {
     let input = String::from("foo: %%bar");
@@ -85,4 +222,145 @@ mod tests {
     let actual = tokenize(input);
     assert_eq!(expected, actual);
   }
+
+  #[test]
+  fn test_literals_around_numeric_placeholders() {
+    let input = String::from("%l.%c:(%c)");
+    let expected = vec![
+      String::from("%l"),
+      String::from("."),
+      String::from("%c"),
+      String::from(":("),
+      String::from("%c"),
+      String::from(")"),
+    ];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_counted_whitespace() {
+    let input = String::from("%m%.{2}%f");
+    let expected = vec!["%m", "%.{2}", "%f"];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_braces_after_whitespace() {
+    let input = String::from("%.{x}");
+    let expected = vec!["%.", "{x}"];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_dash_between_numeric_placeholders() {
+    let input = String::from("%l:%c-%C:");
+    let expected = vec!["%l", ":", "%c", "-", "%C", ":"];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_literal_percent_sign_before_placeholder() {
+    let input = String::from("%%%f");
+    let expected = vec![String::from("%%"), String::from("%f")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_consecutive_literal_percent_signs() {
+    let input = String::from("%%%%");
+    let expected = vec![String::from("%%"), String::from("%%")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_backslash_escaped_placeholder() {
+    let input = String::from(r"\%f: %f");
+    let expected = vec![String::from("%%"), String::from("f: "), String::from("%f")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_backslash_escape_before_placeholder() {
+    let input = String::from(r"\%%f");
+    let expected = vec![String::from("%%"), String::from("%f")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_escaped_backslash_before_placeholder() {
+    let input = String::from(r"\\%f");
+    let expected = vec![String::from(r"\"), String::from("%f")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_double_percent_sign_is_not_unescaped() {
+    let input = String::from("%%");
+    let expected = vec![String::from("%%")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_backslashes_of_custom_regex_are_kept() {
+    let input = String::from(r"%m(/a\\b\%/)\%");
+    let expected = vec![String::from(r"%m(/a\\b\%/)"), String::from("%%")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_lone_backslash_is_literal() {
+    let input = String::from(r"a\b%f");
+    let expected = vec![String::from(r"a\b"), String::from("%f")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
+
+  #[test]
+  fn test_placeholders_are_two_characters_long() {
+    let tests = vec![
+      ("%fl", vec!["%f", "l"]),
+      ("%f%l", vec!["%f", "%l"]),
+      ("%flc%m", vec!["%f", "lc", "%m"]),
+      ("%m%%", vec!["%m", "%%"]),
+      ("%ll%l", vec!["%l", "l", "%l"]),
+    ];
+    for (input, expected) in tests {
+      let actual = tokenize(String::from(input));
+      assert_eq!(expected, actual, "{}", input);
+    }
+  }
+
+  #[test]
+  fn test_regex_override_is_a_placeholder() {
+    let tests = vec![
+      (r"%f(/\S+\.rs/):%l", vec![r"%f(/\S+\.rs/)", ":", "%l"]),
+      (r"%m(/[^)]+/)%l", vec![r"%m(/[^)]+/)", "%l"]),
+      ("%f(%l)", vec!["%f", "(", "%l", ")"]),
+      ("%%f(/a/)", vec!["%%", "f(/a/)"]),
+      ("%f(/)", vec!["%f(/)"]),
+    ];
+    for (input, expected) in tests {
+      let actual = tokenize(String::from(input));
+      assert_eq!(expected, actual, "{}", input);
+    }
+  }
+
+  #[test]
+  fn test_literal_percent_sign_before_letter() {
+    let input = String::from("%%f");
+    let expected = vec![String::from("%%"), String::from("f")];
+    let actual = tokenize(input);
+    assert_eq!(expected, actual);
+  }
 }
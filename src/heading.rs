@@ -0,0 +1,65 @@
+use regex::Regex;
+
+/// Search tools may group matches under the name of their file (think
+/// of `rg --heading`, its default in a terminal): the name is written
+/// back in front of each line of the group so that every match stands
+/// on its own. A group ends at the first blank line, `--` only
+/// separates context within it.
+pub fn expand_headings(input: String) -> String {
+  lazy_static! {
+    static ref RE: Regex = Regex::new(r"^\d+[:-]").unwrap();
+  }
+  let mut heading = None;
+  input
+    .lines()
+    .map(|line| {
+      match line {
+        "" => heading = None,
+        "--" => (),
+        line if RE.is_match(line) => {
+          if let Some(heading) = heading {
+            return format!("{}:{}", heading, line);
+          }
+        }
+        line => heading = Some(line),
+      };
+      line.to_string()
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_flat_output_is_untouched() {
+    let input = String::from("a.c:1:foo\nb.c:2:bar");
+    assert_eq!(input.clone(), expand_headings(input))
+  }
+
+  #[test]
+  fn test_groups_are_expanded() {
+    let input = String::from("a.c\n1:2:foo\n3:4:bar\n\nb.c\n5:6:baz\n");
+    assert_eq!(
+      "a.c\na.c:1:2:foo\na.c:3:4:bar\n\nb.c\nb.c:5:6:baz",
+      expand_headings(input)
+    )
+  }
+
+  #[test]
+  fn test_context_lines_are_expanded() {
+    let input = String::from("a.c\n1-before\n2:foo\n--\n9:bar");
+    assert_eq!(
+      "a.c\na.c:1-before\na.c:2:foo\n--\na.c:9:bar",
+      expand_headings(input)
+    )
+  }
+
+  #[test]
+  fn test_numbers_outside_of_groups() {
+    let input = String::from("1:foo\n\n2:bar");
+    assert_eq!(input.clone(), expand_headings(input))
+  }
+}
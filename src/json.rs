@@ -0,0 +1,147 @@
+use crate::entry::Entry;
+use crate::entry::Kind;
+use serde_json::Value;
+
+/// Read diagnostics from tools emitting JSON Lines instead of text: each
+/// line is a document and the schema is recognized from its fields.
+/// Lines that are not JSON, or that do not hold a diagnostic, are
+/// skipped just like unmatched text is. A non-empty static filename
+/// replaces every path.
+pub fn parse_json(input: impl AsRef<str>, file: impl AsRef<str>) -> Vec<Entry> {
+  input
+    .as_ref()
+    .lines()
+    .filter_map(|line| serde_json::from_str::<Value>(line).ok())
+    .filter_map(|document| diagnostic(&document))
+    .map(|mut entry| {
+      if !file.as_ref().is_empty() {
+        entry.file = file.as_ref().to_string();
+      }
+      entry
+    })
+    .collect()
+}
+
+/// Cargo wraps compiler diagnostics (`cargo check --message-format
+/// json`), rustc emits them as is (`rustc --error-format json`).
+fn diagnostic(document: &Value) -> Option<Entry> {
+  match document.get("reason").and_then(Value::as_str) {
+    Some("compiler-message") => rustc(document.get("message")?),
+    Some(_) => None,
+    None => rustc(document),
+  }
+}
+
+/// A diagnostic is located by its primary span, the one carets point
+/// to: other spans only give context (think of "value moved here"). The
+/// first span stands in when none is marked. Diagnostics without any
+/// span (such as "aborting due to previous error") are left out.
+fn rustc(message: &Value) -> Option<Entry> {
+  let spans = message.get("spans")?.as_array()?;
+  let span = spans
+    .iter()
+    .find(|span| span.get("is_primary").and_then(Value::as_bool) == Some(true))
+    .or_else(|| spans.first())?;
+  let mut entry = Entry::new();
+  entry.file = span.get("file_name")?.as_str()?.to_string();
+  entry.line = number(span, "line_start")?;
+  entry.column = number(span, "column_start")?;
+  entry.end_line = number(span, "line_end").filter(|line| *line != entry.line);
+  entry.end_column = number(span, "column_end");
+  entry.kind = level(message.get("level")?.as_str()?)?;
+  entry.code = message
+    .get("code")
+    .and_then(|code| code.get("code"))
+    .and_then(Value::as_str)
+    .map(String::from);
+  entry.message = message.get("message")?.as_str()?.to_string();
+  Some(entry)
+}
+
+/// Help is a kind of note. Internal compiler errors are fatal.
+fn level(level: &str) -> Option<Kind> {
+  match level {
+    "note" | "help" => Some(Kind::Note),
+    "warning" => Some(Kind::Warning),
+    "error" => Some(Kind::Error),
+    level if level.starts_with("error: internal") => Some(Kind::Fatal),
+    _ => None,
+  }
+}
+
+/// Positions beyond `u32` are saturated, like in text input.
+fn number(value: &Value, field: &str) -> Option<u32> {
+  value
+    .get(field)?
+    .as_u64()
+    .map(|n| n.min(u32::MAX as u64) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const RUSTC: &str = r#"{"message":"unused variable: `y`","code":{"code":"unused_variables","explanation":null},"level":"warning","spans":[{"file_name":"src/main.rs","line_start":3,"line_end":3,"column_start":9,"column_end":10}]}"#;
+
+  #[test]
+  fn test_rustc_diagnostic() {
+    let actual = parse_json(RUSTC, "");
+    assert_eq!(
+      vec![String::from(
+        "src/main.rs:3:9: warning: unused variable: `y` [unused_variables]"
+      )],
+      actual.iter().map(Entry::to_string).collect::<Vec<_>>()
+    )
+  }
+
+  #[test]
+  fn test_primary_span() {
+    let input = r#"{"message":"borrow of moved value: `v`","level":"error","spans":[{"file_name":"a.rs","line_start":8,"line_end":8,"column_start":21,"column_end":22,"is_primary":false},{"file_name":"a.rs","line_start":9,"line_end":9,"column_start":26,"column_end":27,"is_primary":true}]}"#;
+    let actual = &parse_json(input, "")[0];
+    assert_eq!((9, 26), (actual.line, actual.column))
+  }
+
+  #[test]
+  fn test_cargo_message() {
+    let input = format!(
+      r#"{{"reason":"compiler-message","package_id":"foo","message":{}}}"#,
+      RUSTC
+    );
+    assert_eq!(1, parse_json(input, "").len())
+  }
+
+  #[test]
+  fn test_other_cargo_reasons_are_skipped() {
+    let input = r#"{"reason":"build-finished","success":false}"#;
+    assert!(parse_json(input, "").is_empty())
+  }
+
+  #[test]
+  fn test_text_lines_are_skipped() {
+    let input = format!("   Compiling foo\n{}\n", RUSTC);
+    assert_eq!(1, parse_json(input, "").len())
+  }
+
+  #[test]
+  fn test_diagnostic_without_span_is_skipped() {
+    let input = r#"{"message":"aborting due to 1 previous error","level":"error","spans":[]}"#;
+    assert!(parse_json(input, "").is_empty())
+  }
+
+  #[test]
+  fn test_static_filename() {
+    assert_eq!("foo.rs", parse_json(RUSTC, "foo.rs")[0].file)
+  }
+
+  #[test]
+  fn test_levels() {
+    assert_eq!(
+      (Some(Kind::Note), Some(Kind::Fatal), None),
+      (
+        level("help"),
+        level("error: internal compiler error"),
+        level("failure-note")
+      )
+    )
+  }
+}
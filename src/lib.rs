@@ -24,43 +24,125 @@
 //!
 //! ### Supported placeholders:
 //! - `%f`: filename
+//! - `%q`: filename, possibly enclosed in double quotes so that it may
+//!   contain separators
 //! - `%l`: line number
+//! - `%L`: end line number, for tools reporting ranges (`%l,%L`)
 //! - `%c`: column number
-//! - `%k`: error kind (warning or error)
-//! - `%m`: error message
-//! - `%.`: sequence of whitespace characters (including new lines)
-//! - `%*`: anything
-//! - ...every other sequence will be treated as literal.
+//! - `%C`: end column number, for tools reporting ranges (`%c-%C`)
+//! - `%e`: diagnostic code or rule name (`TS2322`, `E0308`...), optional
+//!   along with its brackets when written `[%e]`
+//! - `%k`: error kind (warning, error, note, fatal, panic or internal,
+//!   as well as fatal error, internal error, internal compiler error and
+//!   parse error), or symbol (`✖`, `✗`, `✘` and `❌` for errors, `⚠` for
+//!   warnings, `ℹ` for notes), entries are errors when it is missing
+//! - `%s`: numeric severity level, 2 and above are errors and anything
+//!   lower is a warning (see `SeverityMap` to change the thresholds)
+//! - `%o`: byte offset from the beginning of the file, translated to a
+//!   line and column when the file can be read
+//! - `%m`: error message, up to the literal that follows if any (think
+//!   of a trailing `[rule]` or of message-first formats such as
+//!   `%m at %f:%l`), up to the end of the line otherwise
+//! - `%M`: error message wrapped over the following indented lines,
+//!   which are joined with single spaces
+//! - `%T`: timestamp (ISO 8601 or `[HH:MM:SS]`), skipped
+//! - `%P`: progress counter of build tools (`[3/10]` or `[ 42%]`), skipped
+//! - `%.`: sequence of whitespace characters (including new lines), may
+//!   be missing at the end of the input when it closes the errorformat
+//! - `%.{2}`: sequence of whitespace characters holding exactly that
+//!   many line breaks, for records told apart by blank lines
+//! - `%~`: optional sequence of whitespace characters
+//! - `%*`: anything on the same line, several in a row are the same as a
+//!   single one
+//! - `%+`: anything, line breaks included, as little as possible
+//! - `%$`: end of a record (end of line, including the line break), pins
+//!   the last field to the end of the line
+//! - `%(a|b)`: either one of the given literals, which cannot contain
+//!   `|` nor `)` (use `%%` for a percent sign)
+//! - `%f(/\S+\.rs/)`: any data placeholder followed by a custom regex
+//!   between `(/` and `/)`, which replaces the built-in one (groups must
+//!   be non-capturing: `(?:...)`)
+//! - `%t`: literal tab character
+//! - `%%` or `\%`: literal percent sign, `\\` being a literal
+//!   backslash
+//! - ...every other sequence will be treated as literal, except for a
+//!   percent sign followed by an unknown character which is an error.
 
 #[macro_use]
 extern crate lazy_static;
 
-use regex::Captures;
+use regex::CaptureLocations;
 use regex::Error;
-use regex::Match;
 use regex::Regex;
+use std::collections::HashSet;
 use std::convert::TryInto;
+use std::fmt;
+use std::iter;
+use std::time::Instant;
 
+mod ansi;
+mod changes;
+mod config;
+mod diff;
 mod entry;
 mod errfmt;
+mod heading;
+mod json;
+mod lsp;
+mod noise;
+mod presets_file;
+mod rewrite;
+mod severity;
 mod shape;
+mod stats;
+mod template;
 mod token;
+#[cfg(feature = "wasm")]
+mod wasm;
 
-use entry::Entry;
-use entry::Kind;
 use shape::Shape;
+use stats::Tally;
 use token::Token;
 
+pub use crate::ansi::strip_ansi;
+pub use crate::changes::Changes;
+pub use crate::config::Config;
+pub use crate::diff::parse_diff;
+pub use crate::entry::fold_notes;
+pub use crate::entry::render_grouped;
+pub use crate::entry::Case;
+pub use crate::entry::ColumnUnit;
+pub use crate::entry::Entry;
+pub use crate::entry::Format;
+pub use crate::entry::Kind;
+pub use crate::errfmt::preset;
+pub use crate::errfmt::presets;
 pub use crate::errfmt::ESLINT_ERRFMT;
 pub use crate::errfmt::GOLINT_ERRFMT;
+pub use crate::errfmt::GREP_ERRFMT;
 pub use crate::errfmt::PASSTHROUGH_ERRFMT;
 pub use crate::errfmt::PHP_ERRFMT;
+pub use crate::errfmt::RIPGREP_ERRFMT;
+pub use crate::errfmt::RUSTC_ERRFMT;
 pub use crate::errfmt::RUSTFMT_ERRFMT;
 pub use crate::errfmt::SHELLCHECK_ERRFMT;
+pub use crate::errfmt::TSC_ERRFMT;
+pub use crate::heading::expand_headings;
+pub use crate::json::parse_json;
+pub use crate::lsp::parse_lsp;
+pub use crate::noise::skip_noise;
+pub use crate::presets_file::PresetsFile;
+pub use crate::rewrite::PathRewrite;
+pub use crate::severity::SeverityMap;
+pub use crate::stats::ParseStats;
+pub use crate::stats::Timings;
+pub use crate::template::Template;
 
 /// Entrypoint of the program: configure the errorformat string and
 /// de-facto filename then filter input to re-shape it into the expected
-/// format.
+/// format. Lines and columns are at least 1, as expected by lint.kak.
+/// An empty errorformat string is rejected rather than defaulting to
+/// `PASSTHROUGH_ERRFMT`: it is most likely a mistake.
 ///
 /// # Example: simple error message
 ///
@@ -83,88 +165,846 @@ pub use crate::errfmt::SHELLCHECK_ERRFMT;
 /// );
 /// assert_eq!(String::from("/tmp/anotherfile:3:1: error: syntax error"), messages.unwrap()[0]);
 /// ```
-pub fn run(input: String, errfmt: String, file: String) -> Result<Vec<String>, String> {
+///
+/// # Example: empty errorformat string
+///
+/// ```
+/// assert_eq!(Err(errfmt::ErrfmtError::Empty), errfmt::run("foo", "", ""));
+/// ```
+///
+/// # Example: borrowed strings
+///
+/// ```
+/// let messages = errfmt::run("/tmp/myfile:3: syntax error", "%f:%l: %m", "");
+/// assert_eq!(String::from("/tmp/myfile:3:1: error: syntax error"), messages.unwrap()[0]);
+/// ```
+pub fn run(
+  input: impl AsRef<str>,
+  errfmt: impl AsRef<str>,
+  file: impl AsRef<str>,
+) -> Result<Vec<String>, ErrfmtError> {
+  run_with(input, &Config::new(errfmt).file(file))
+}
+
+/// Same as `run` with every option spelled out in a `Config`.
+///
+/// ```
+/// let config = errfmt::Config::new("%f:%l:%c: %m").min_position(0);
+/// let messages = errfmt::run_with("a.c:0:0: oops", &config).unwrap();
+/// assert_eq!(vec![String::from("a.c:0:0: error: oops")], messages);
+/// ```
+pub fn run_with(input: impl AsRef<str>, config: &Config) -> Result<Vec<String>, ErrfmtError> {
   Ok(
-    Parser::new(errfmt, file)
-      .parse(input)
-      .map_err(|err| err.to_string())?
-      .iter()
-      .map(|entry| entry.to_string())
+    parse_with(input, config)?
+      .into_iter()
+      .map(|entry| {
+        entry
+          .clamp(config.min_position)
+          .render(config.output, config.case)
+      })
+      .collect(),
+  )
+}
+
+/// Same as `run` but also report how much of the input was matched,
+/// which scores an errorformat string against a sample of output.
+///
+/// ```
+/// let (messages, stats) = errfmt::run_with_stats(
+///   "/tmp/myfile:3: syntax error\nmake: *** [all] Error 1",
+///   "%f:%l: %m",
+///   ""
+/// ).unwrap();
+/// assert_eq!(1, messages.len());
+/// assert_eq!((27, 51), (stats.matched_bytes, stats.bytes));
+/// ```
+pub fn run_with_stats(
+  input: impl AsRef<str>,
+  errfmt: impl AsRef<str>,
+  file: impl AsRef<str>,
+) -> Result<(Vec<String>, ParseStats), ErrfmtError> {
+  let (entries, stats) = parse_with_stats(input, &Config::new(errfmt).file(file))?;
+  let messages = entries
+    .into_iter()
+    .map(|entry| entry.clamp(1).to_string())
+    .collect();
+  Ok((messages, stats))
+}
+
+/// Same as `run` but stop before rendering: this gives a chance to
+/// post-process the entries. Numbers are left as reported by the tool.
+///
+/// # Example: force the kind of every entry
+///
+/// ```
+/// let mut entries = errfmt::parse(
+///   String::from("/tmp/myfile warning on line 3 column 1: unused variable"),
+///   String::from("%f %k on line %l column %c: %m"),
+///   String::new()
+/// ).unwrap();
+/// entries.iter_mut().for_each(|entry| entry.kind = errfmt::Kind::Error);
+/// assert_eq!(String::from("/tmp/myfile:3:1: error: unused variable"), entries[0].to_string());
+/// ```
+pub fn parse(
+  input: impl AsRef<str>,
+  errfmt: impl AsRef<str>,
+  file: impl AsRef<str>,
+) -> Result<Vec<Entry>, ErrfmtError> {
+  parse_with(input, &Config::new(errfmt).file(file))
+}
+
+/// Same as `parse` with every option spelled out in a `Config`: only
+/// those affecting parsing apply, rendering is left to the caller.
+///
+/// ```
+/// let config = errfmt::Config::new("%f:%l: %s %m").severity("error=1".parse().unwrap());
+/// let entries = errfmt::parse_with("a.js:3: 1 oops", &config).unwrap();
+/// assert_eq!(errfmt::Kind::Error, entries[0].kind);
+/// ```
+pub fn parse_with(input: impl AsRef<str>, config: &Config) -> Result<Vec<Entry>, ErrfmtError> {
+  let parser = Parser::from_config(config);
+  parser.check()?;
+  Ok(parser.parse(input)?)
+}
+
+/// Same as `parse` but each entry is handed over to the given closure
+/// as soon as it is read instead of being collected.
+///
+/// # Example: count errors
+///
+/// ```
+/// let mut errors = 0;
+/// errfmt::parse_each("a.c:1: error: foo\na.c:2: warning: bar", "%f:%l: %k: %m", "", |entry| {
+///   if entry.kind == errfmt::Kind::Error {
+///     errors += 1;
+///   }
+/// })
+/// .unwrap();
+/// assert_eq!(1, errors);
+/// ```
+pub fn parse_each<F>(
+  input: impl AsRef<str>,
+  errfmt: impl AsRef<str>,
+  file: impl AsRef<str>,
+  f: F,
+) -> Result<(), ErrfmtError>
+where
+  F: FnMut(Entry),
+{
+  let parser = Parser::new(errfmt.as_ref().to_string(), file.as_ref().to_string());
+  parser.check()?;
+  Ok(parser.parse_each(input, f)?)
+}
+
+/// Same as `parse_with` but also report how much of the input was
+/// matched. Entries made of unmatched lines (see `Config::lenient`) do
+/// not count as matches.
+///
+/// # Example: tune an errorformat string
+///
+/// ```
+/// let config = errfmt::Config::new("%f:%l: %m");
+/// let input = "/tmp/myfile:3: syntax error\nmake: *** [all] Error 1";
+/// let (_, stats) = errfmt::parse_with_stats(input, &config).unwrap();
+/// assert_eq!((1, 2, 1), (stats.entries, stats.lines, stats.unmatched_lines()));
+/// ```
+///
+/// # Example: interleaved stderr and stdout
+///
+/// ```
+/// let config = errfmt::Config::new(errfmt::RUSTC_ERRFMT).loose(true);
+/// let input = "error: oops\nCompiling bar\n --> src/main.rs:2:3";
+/// let (entries, _) = errfmt::parse_with_stats(input, &config).unwrap();
+/// assert_eq!("src/main.rs:2:3: error: oops", entries[0].to_string());
+/// ```
+///
+/// # Example: keep pathless lines
+///
+/// ```
+/// let config = errfmt::Config::new(errfmt::PASSTHROUGH_ERRFMT).file("a.c").lenient(true);
+/// let (entries, stats) = errfmt::parse_with_stats("a.c:1:2: error: foo\nSegfault", &config).unwrap();
+/// assert_eq!("a.c:1:1: error: Segfault", entries[1].to_string());
+/// assert_eq!(1, stats.entries);
+/// ```
+pub fn parse_with_stats(
+  input: impl AsRef<str>,
+  config: &Config,
+) -> Result<(Vec<Entry>, ParseStats), ErrfmtError> {
+  let parser = Parser::from_config(config);
+  parser.check()?;
+  Ok(parser.parse_with_stats(input)?)
+}
+
+/// Degenerate parsing for tools that only emit an opaque blob: the whole
+/// input is a single entry, its lines joined so that it fits a line of
+/// output. Blank input gives no entry.
+///
+/// ```
+/// let entries = errfmt::parse_whole("Segmentation fault\n  (core dumped)\n", "main.c");
+/// assert_eq!("main.c:1:1: error: Segmentation fault (core dumped)", entries[0].to_string());
+/// ```
+pub fn parse_whole(input: impl AsRef<str>, file: impl AsRef<str>) -> Vec<Entry> {
+  let message = input
+    .as_ref()
+    .lines()
+    .map(str::trim)
+    .filter(|line| !line.is_empty())
+    .collect::<Vec<_>>()
+    .join(" ");
+  if message.is_empty() {
+    return Vec::new();
+  }
+  let mut entry = Entry::new();
+  entry.file = file.as_ref().to_string();
+  entry.message = message;
+  vec![entry]
+}
+
+/// Leave out the entries already listed in a previous output, so that
+/// new ones can be appended to it. The list is read back with
+/// `PASSTHROUGH_ERRFMT`, which only suits the default output format,
+/// and entries are compared by id (file, position and message, whatever
+/// the kind): lines it does not understand are ignored.
+///
+/// ```
+/// let entries = errfmt::parse("a.c:1: foo\na.c:2: bar", "%f:%l: %m", "").unwrap();
+/// let entries = errfmt::exclude(entries, "a.c:1:1: error: foo\n").unwrap();
+/// assert_eq!("a.c:2:1: error: bar", entries[0].to_string());
+/// assert_eq!(1, entries.len());
+/// ```
+pub fn exclude(entries: Vec<Entry>, known: impl AsRef<str>) -> Result<Vec<Entry>, ErrfmtError> {
+  let known = parse(known, PASSTHROUGH_ERRFMT, "")?
+    .iter()
+    .map(Entry::id)
+    .collect::<HashSet<_>>();
+  Ok(
+    entries
+      .into_iter()
+      .filter(|entry| !known.contains(&entry.id()))
       .collect(),
   )
 }
 
+/// Report on how an errorformat string reads a sample, to shorten the
+/// feedback loop while writing one: the compiled regex is followed by
+/// every match with its line number, the text captured by each
+/// placeholder and the resulting entry.
+///
+/// ```
+/// let report = errfmt::explain("a.c:3: oops", "%f:%l: %m", "").unwrap();
+/// assert!(report.contains("  %f: \"a.c\"\n"));
+/// assert!(report.ends_with("  => a.c:3:1: error: oops\n"));
+/// ```
+pub fn explain(
+  input: impl AsRef<str>,
+  errfmt: impl AsRef<str>,
+  file: impl AsRef<str>,
+) -> Result<String, ErrfmtError> {
+  let parser = Parser::new(errfmt.as_ref().to_string(), file.as_ref().to_string());
+  parser.check()?;
+  Ok(parser.explain(input)?)
+}
+
+/// Same as `explain` with a configuration built from the errorformat
+/// string. The note format is not explained.
+///
+/// ```
+/// let config = errfmt::Config::new("%f:%l: %m").size_limit(usize::MAX);
+/// let report = errfmt::explain_with("a.c:3: oops", &config).unwrap();
+/// assert!(report.ends_with("  => a.c:3:1: error: oops\n"));
+/// ```
+pub fn explain_with(input: impl AsRef<str>, config: &Config) -> Result<String, ErrfmtError> {
+  let parser = Parser::from_config(config);
+  parser.check()?;
+  Ok(parser.explain(input)?)
+}
+
+/// Translate an errorformat string to the regex used to match error
+/// messages. Every placeholder carrying data (file, line, column, kind
+/// and message) is a capture group, in order of appearance.
+///
+/// # Example: highlight messages
+///
+/// ```
+/// let regex = errfmt::compile("%f:%l: %m").unwrap();
+/// let captures = regex.captures("/tmp/myfile:3: syntax error").unwrap();
+/// assert_eq!("/tmp/myfile", &captures[1]);
+/// assert_eq!("syntax error", &captures[3]);
+/// ```
+pub fn compile(errfmt: &str) -> Result<Regex, ErrfmtError> {
+  let shape = shape(errfmt.to_string());
+  check(&shape)?;
+  Ok(shape.try_into()?)
+}
+
+/// Whether the errorformat string extracts the kind of the entries,
+/// from a word or from a numeric severity. When it does not, every
+/// entry is an error.
+///
+/// ```
+/// assert!(!errfmt::captures_kind(errfmt::GOLINT_ERRFMT));
+/// assert!(errfmt::captures_kind(errfmt::PASSTHROUGH_ERRFMT));
+/// ```
+pub fn captures_kind(errfmt: &str) -> bool {
+  shape(errfmt.to_string())
+    .iter()
+    .any(|token| matches!(token.base(), Token::Kind | Token::Severity))
+}
+
+/// Everything that can go wrong with an errorformat string.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ErrfmtError {
+  /// An errorformat string without any token would match everywhere.
+  Empty,
+  /// A percent sign followed by a character that has no meaning.
+  UnknownPlaceholder(String),
+  /// A custom regex that cannot be used, along with the reason.
+  InvalidOverride(String, String),
+  /// Two placeholders that cannot be told apart in the input.
+  Ambiguous(String, String),
+  /// The compiled regex exceeds the given size limit.
+  TooBig(usize),
+  /// Any other error from the regex engine.
+  Regex(String),
+}
+
+impl fmt::Display for ErrfmtError {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Empty => write!(f, "empty errorformat: nothing to match"),
+      Self::UnknownPlaceholder(value) => write!(
+        f,
+        "unknown placeholder {}: use %% (or \\%) for a literal percent sign",
+        value
+      ),
+      Self::InvalidOverride(value, err) => write!(f, "invalid regex in {}: {}", value, err),
+      Self::Ambiguous(left, right) => write!(
+        f,
+        "ambiguous errorformat: {} and {} must be separated",
+        left, right
+      ),
+      Self::TooBig(limit) => write!(
+        f,
+        "errorformat is too complex: compiled regex exceeds {} bytes",
+        limit
+      ),
+      Self::Regex(err) => write!(f, "invalid errorformat: {}", err),
+    }
+  }
+}
+
+impl std::error::Error for ErrfmtError {}
+
+impl From<Error> for ErrfmtError {
+  fn from(err: Error) -> Self {
+    match err {
+      Error::CompiledTooBig(limit) => Self::TooBig(limit),
+      err => Self::Regex(err.to_string()),
+    }
+  }
+}
+
+/// Read the configuration (errorformat string) and compute the shape
+/// of an error message.
+fn shape(errfmt: String) -> Shape<Token> {
+  refine(bracket_codes(collapse(
+    errfmt::tokenize(errfmt)
+      .into_iter()
+      .map(Token::from)
+      .collect(),
+  )))
+  .into_iter()
+  .fold(Shape::new(), |acc, t| acc.push(t))
+}
+
+/// Adjacent skipping tokens that match the same input as a single one
+/// are merged: they would only give the regex engine more ways to
+/// split the very same text.
+fn collapse(tokens: Vec<Token>) -> Vec<Token> {
+  tokens.into_iter().fold(Vec::new(), |mut acc, token| {
+    match (acc.last(), &token) {
+      (Some(Token::Wildcard), Token::Wildcard)
+      | (Some(Token::MultilineWildcard), Token::MultilineWildcard)
+      | (Some(Token::OptionalWhitespace), Token::OptionalWhitespace)
+      | (Some(Token::Whitespace), Token::OptionalWhitespace) => (),
+      (Some(Token::OptionalWhitespace), Token::Whitespace) => *acc.last_mut().unwrap() = token,
+      _ => acc.push(token),
+    };
+    acc
+  })
+}
+
+/// A code between brackets (think of rustc's `error[E0308]:`) is
+/// optional, brackets included: the brackets are taken out of the
+/// surrounding literals and read along with the code.
+fn bracket_codes(tokens: Vec<Token>) -> Vec<Token> {
+  let mut iter = tokens.into_iter().peekable();
+  let mut acc: Vec<Token> = Vec::new();
+  while let Some(token) = iter.next() {
+    let bracketed = matches!(token, Token::Code)
+      && matches!(acc.last(), Some(Token::Literal(before)) if before.ends_with('['))
+      && matches!(iter.peek(), Some(Token::Literal(after)) if after.starts_with(']'));
+    if !bracketed {
+      acc.push(token);
+      continue;
+    }
+    if let Some(Token::Literal(before)) = acc.pop() {
+      let before = &before[..before.len() - 1];
+      if !before.is_empty() {
+        acc.push(Token::Literal(before.to_string()));
+      }
+    }
+    acc.push(Token::BracketedCode);
+    if let Some(Token::Literal(after)) = iter.next() {
+      if after.len() > 1 {
+        acc.push(Token::Literal(after[1..].to_string()));
+      }
+    }
+  }
+  acc
+}
+
+/// Some tokens behave differently depending on their neighbours. The
+/// last token is the boundary of a record: whitespace there must
+/// tolerate input lacking a final line break and a literal may be
+/// missing at the end of a line. A message directly followed by a
+/// literal (or alternation) stops at the first occurrence of it.
+fn refine(mut tokens: Vec<Token>) -> Vec<Token> {
+  let len = tokens.len();
+  if len > 0 {
+    tokens[len - 1] = match tokens[len - 1].clone() {
+      Token::Whitespace => Token::TrailingWhitespace,
+      Token::Literal(value) if len > 1 => Token::Delimiter(value),
+      token => token,
+    };
+  }
+  for n in 1..len {
+    if let (Token::Message, Token::Literal(_))
+    | (Token::Message, Token::Delimiter(_))
+    | (Token::Message, Token::Alternation(_)) = (&tokens[n - 1], &tokens[n])
+    {
+      tokens[n - 1] = Token::DelimitedMessage;
+    }
+  }
+  tokens
+}
+
+/// Reject shapes that would silently produce wrong data. Two adjacent
+/// numbers, for instance, could be split anywhere. Unknown placeholders
+/// are reserved for future use. An empty shape would match between
+/// every character of the input. Custom regexes are checked on their
+/// own so that errors point at them.
+fn check(shape: &Shape<Token>) -> Result<(), ErrfmtError> {
+  if shape.is_empty() {
+    return Err(ErrfmtError::Empty);
+  }
+  if let Some((value, err)) = shape.iter().find_map(Token::invalid_override) {
+    return Err(ErrfmtError::InvalidOverride(value, err));
+  }
+  if let Some(value) = shape.iter().find_map(Token::unknown_placeholder) {
+    return Err(ErrfmtError::UnknownPlaceholder(value));
+  }
+  match shape
+    .windows(2)
+    .find(|pair| pair[0].is_numeric() && pair[1].is_numeric())
+  {
+    Some(pair) => Err(ErrfmtError::Ambiguous(
+      pair[0].to_string(),
+      pair[1].to_string(),
+    )),
+    None => Ok(()),
+  }
+}
+
 /// Parser is responsible for building a set of entries matching the
 /// extracted error messages.
 #[derive(Debug)]
 struct Parser {
   shape: Shape<Token>,
   file: String,
+  severity: SeverityMap,
+  lenient: bool,
+  kind: Kind,
+  thousands: Option<char>,
+  size_limit: usize,
+  notes: Option<Box<Parser>>,
 }
 
 impl Parser {
-  /// Read the configuration (errorformat string) and compute the shape
-  /// of an error message.
+  /// The shape of an error message is computed once and for all.
   fn new(errfmt: String, file: String) -> Self {
     Parser {
-      shape: errfmt::tokenize(errfmt)
-        .into_iter()
-        .map(Token::from)
-        .fold(Shape::new(), |acc, t| acc.push(t)),
+      shape: shape(errfmt),
       file,
+      severity: SeverityMap::default(),
+      lenient: false,
+      kind: Kind::Error,
+      thousands: None,
+      size_limit: shape::REGEX_MAX_SIZE,
+      notes: None,
+    }
+  }
+
+  /// The note format is read the same way.
+  fn from_config(config: &Config) -> Self {
+    let parser = |errfmt: &String| {
+      let parser = Parser {
+        severity: config.severity.clone(),
+        lenient: config.lenient,
+        kind: config.default_kind,
+        size_limit: config.size_limit,
+        ..Parser::new(errfmt.clone(), config.file.clone())
+      };
+      let parser = match config.ignore_indent {
+        true => parser.indented(),
+        false => parser,
+      };
+      let parser = match config.thousands {
+        Some(separator) => parser.grouped(separator),
+        None => parser,
+      };
+      match config.loose {
+        true => parser.loose(),
+        false => parser,
+      }
+    };
+    Parser {
+      notes: config.notes.as_ref().map(|notes| Box::new(parser(notes))),
+      ..parser(&config.errfmt)
+    }
+  }
+
+  /// Both formats must be valid.
+  fn check(&self) -> Result<(), ErrfmtError> {
+    check(&self.shape)?;
+    match &self.notes {
+      Some(notes) => check(&notes.shape),
+      None => Ok(()),
+    }
+  }
+
+  /// Whitespace between fields may span unrelated lines.
+  fn loose(mut self) -> Self {
+    self.shape = self
+      .shape
+      .iter()
+      .map(|token| match token {
+        Token::Whitespace => Token::LooseWhitespace,
+        token => token.clone(),
+      })
+      .fold(Shape::new(), |acc, t| acc.push(t));
+    self
+  }
+
+  /// Records may be indented (think of `go test`), the indentation is
+  /// then left out of the first field. An empty shape stays empty.
+  fn indented(mut self) -> Self {
+    if !self.shape.is_empty() {
+      self.shape = iter::once(Token::Indentation)
+        .chain(self.shape.iter().cloned())
+        .fold(Shape::new(), |acc, t| acc.push(t));
+    }
+    self
+  }
+
+  /// Lines and columns may have their digits grouped by thousands. A
+  /// custom regex is left alone.
+  fn grouped(mut self, separator: char) -> Self {
+    let pattern = format!(
+      r"\d{{1,3}}(?:{}\d{{3}})+|\d+",
+      regex::escape(&separator.to_string())
+    );
+    self.shape = self
+      .shape
+      .iter()
+      .map(|token| match token {
+        Token::Line | Token::EndLine | Token::Column | Token::EndColumn => {
+          Token::Override(Box::new(token.clone()), pattern.clone())
+        }
+        token => token.clone(),
+      })
+      .fold(Shape::new(), |acc, t| acc.push(t));
+    self.thousands = Some(separator);
+    self
+  }
+
+  /// Separators between groups of digits are not part of the number.
+  fn number(&self, value: &str) -> u32 {
+    match self.thousands {
+      Some(separator) => parse_number(&value.replace(separator, "")),
+      None => parse_number(value),
     }
   }
 
   /// Build the resulting pattern from the shape and gather the list of
   /// entries matching an error message.
-  fn parse(&self, input: String) -> Result<Vec<Entry>, Error> {
-    self.shape.clone().try_into().map(|r: Regex| {
-      r.captures_iter(&input)
-        .map(|matches| self.build_entry(&matches))
-        .collect()
+  fn parse(&self, input: impl AsRef<str>) -> Result<Vec<Entry>, Error> {
+    self.parse_with_stats(input).map(|(entries, _)| entries)
+  }
+
+  /// Matches are counted along the way, compiling and matching are
+  /// timed apart.
+  fn parse_with_stats(&self, input: impl AsRef<str>) -> Result<(Vec<Entry>, ParseStats), Error> {
+    let input = input.as_ref();
+    let mut entries = Vec::new();
+    let mut tally = Tally::new(input);
+    let start = Instant::now();
+    let (regex, notes) = self.compile()?;
+    let compile = start.elapsed();
+    let start = Instant::now();
+    self.parse_compiled(input, &regex, notes.as_ref(), |entry| {
+      if let Some(span) = entry.span() {
+        tally.record(input, span);
+      }
+      entries.push(entry)
+    });
+    let matching = start.elapsed();
+    Ok((entries, tally.finish(Timings { compile, matching })))
+  }
+
+  /// Entries are handed over one by one as soon as they are built, along
+  /// with those found in the lines left between matches.
+  fn parse_each<F>(&self, input: impl AsRef<str>, f: F) -> Result<(), Error>
+  where
+    F: FnMut(Entry),
+  {
+    let (regex, notes) = self.compile()?;
+    self.parse_compiled(input.as_ref(), &regex, notes.as_ref(), f);
+    Ok(())
+  }
+
+  /// Regexes of the main format and of the note format if any.
+  fn compile(&self) -> Result<(Regex, Option<Regex>), Error> {
+    let regex = self.shape.clone().compile(self.size_limit)?;
+    let notes = match &self.notes {
+      Some(notes) => Some(notes.shape.clone().compile(self.size_limit)?),
+      None => None,
+    };
+    Ok((regex, notes))
+  }
+
+  fn parse_compiled<F>(&self, input: &str, regex: &Regex, notes: Option<&Regex>, mut f: F)
+  where
+    F: FnMut(Entry),
+  {
+    let mut cursor = 0;
+    for_each_match(regex, input, |locations| {
+      let (start, end) = locations.get(0).unwrap();
+      self.leftovers(input, (cursor, start), notes, &mut f);
+      cursor = end;
+      f(self.build_entry(input, locations))
+    });
+    self.leftovers(input, (cursor, input.len()), notes, &mut f);
+  }
+
+  /// Lines that no match touches are first read with the note format,
+  /// if any: its entries become notes and what it leaves goes the same
+  /// way. Otherwise, a lenient parser turns each non-blank line into a
+  /// bare entry.
+  fn leftovers<F>(&self, input: &str, range: (usize, usize), notes: Option<&Regex>, f: &mut F)
+  where
+    F: FnMut(Entry),
+  {
+    let (start, end) = untouched_range(input, range);
+    let region = &input[start..end];
+    match (&self.notes, notes) {
+      (Some(parser), Some(regex)) => {
+        let mut cursor = 0;
+        for_each_match(regex, region, |locations| {
+          let (s, e) = locations.get(0).unwrap();
+          parser.leftovers(region, (cursor, s), None, f);
+          cursor = e;
+          let mut entry = parser.build_entry(region, locations);
+          entry.kind = Kind::Note;
+          entry.span = entry.span.map(|(s, e)| (s + start, e + start));
+          f(entry)
+        });
+        parser.leftovers(region, (cursor, region.len()), None, f);
+      }
+      _ if self.lenient => region
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .for_each(|line| {
+          let mut entry = Entry::new();
+          entry.file = self.file.clone();
+          entry.kind = self.kind;
+          entry.message = line.to_string();
+          f(entry)
+        }),
+      _ => (),
+    }
+  }
+
+  /// Captures are labelled with the placeholder they belong to. Groups
+  /// that did not take part in a match are left out.
+  fn explain(&self, input: impl AsRef<str>) -> Result<String, Error> {
+    let input = input.as_ref();
+    self.shape.clone().compile(self.size_limit).map(|r| {
+      let mut report = format!("regex: {}\n", r);
+      let mut n = 0;
+      for_each_match(&r, input, |locations| {
+        let (start, end) = locations.get(0).unwrap();
+        n += 1;
+        report += &format!(
+          "match {} on line {}: {:?}\n",
+          n,
+          input[..start].matches('\n').count() + 1,
+          &input[start..end]
+        );
+        self
+          .shape
+          .iter()
+          .filter(|token| token.captures())
+          .enumerate()
+          .filter_map(|(n, token)| locations.get(n + 1).map(|(s, e)| (token, &input[s..e])))
+          .for_each(|(token, value)| report += &format!("  {}: {:?}\n", token, value));
+        report += &format!("  => {}\n", self.build_entry(input, locations).clamp(1));
+      });
+      if n == 0 {
+        report += "no match\n";
+      }
+      report
     })
   }
 
   /// Add a new location to the result set by reading its data from
-  /// capture groups.
-  fn build_entry(&self, matches: &Captures) -> Entry {
+  /// capture groups. The span of the whole match is kept around.
+  fn build_entry(&self, input: &str, locations: &CaptureLocations) -> Entry {
+    let mut entry = Entry::new();
+    entry.kind = self.kind;
+    entry.span = locations.get(0);
     self
       .shape
       .iter()
+      .filter(|token| token.captures())
       .enumerate()
       // Ignore the first match as it is the entire string.
-      .map(|(n, token)| (matches.get(n + 1), token))
-      .fold(Entry::new(), |entry, (group, token)| {
-        self.mutate_entry(entry, token, group)
+      .map(|(n, token)| (locations.get(n + 1), token))
+      .fold(entry, |entry, (group, token)| {
+        self.mutate_entry(entry, token, group.map(|(s, e)| &input[s..e]))
       })
   }
 
   /// Update a given entry according to the corresponding token.
   /// Given filename overrides any extracted data in case the linter
   /// cannot handle this. This function will easily panic in case there
-  /// is no matching capture group or if the data could not be converted
-  /// to an integer in the appropriate cases.
-  fn mutate_entry(&self, mut entry: Entry, token: &Token, data: Option<Match>) -> Entry {
-    let parse_str = || data.unwrap().as_str();
-    let parse_u32 = || parse_str().parse::<u32>().unwrap();
+  /// is no matching capture group. A kind that a custom regex captured
+  /// but that means nothing keeps the default one.
+  fn mutate_entry(&self, mut entry: Entry, token: &Token, data: Option<&str>) -> Entry {
     match token {
-      Token::File => {
-        entry.file = if String::is_empty(&self.file) {
-          String::from(parse_str())
-        } else {
-          String::from(&self.file)
-        }
+      Token::File | Token::QuotedFile if !String::is_empty(&self.file) => {
+        entry.file = self.file.clone()
+      }
+      Token::File => entry.file = String::from(data.unwrap()),
+      Token::QuotedFile => entry.file = String::from(unquote(data.unwrap())),
+      Token::Column => entry.column = self.number(data.unwrap()),
+      Token::EndColumn => entry.end_column = Some(self.number(data.unwrap())),
+      Token::Kind => entry.kind = Kind::read(data.unwrap()).unwrap_or(entry.kind),
+      Token::Severity => {
+        let level = parse_number(data.unwrap());
+        entry.level = Some(level);
+        entry.kind = self.severity.kind(level);
       }
-      Token::Column => entry.column = parse_u32(),
-      Token::Kind => entry.kind = Kind::from(parse_str()),
-      Token::Line => entry.line = parse_u32(),
-      Token::Message => entry.message = String::from(parse_str()),
-      Token::Whitespace | Token::Wildcard | Token::Literal(_) => (),
+      Token::Code => entry.code = Some(String::from(data.unwrap())),
+      Token::BracketedCode => entry.code = unbracket(data.unwrap()).map(String::from),
+      Token::Line => entry.line = self.number(data.unwrap()),
+      Token::EndLine => entry.end_line = Some(self.number(data.unwrap())),
+      Token::Offset => entry.offset = Some(parse_number(data.unwrap())),
+      Token::Message | Token::DelimitedMessage => entry.message = String::from(data.unwrap()),
+      Token::ContinuedMessage => entry.message = join_lines(data.unwrap()),
+      Token::Whitespace
+      | Token::Newlines(_)
+      | Token::Indentation
+      | Token::TrailingWhitespace
+      | Token::LooseWhitespace
+      | Token::OptionalWhitespace
+      | Token::Wildcard
+      | Token::MultilineWildcard
+      | Token::Separator
+      | Token::Timestamp
+      | Token::Progress
+      | Token::Literal(_)
+      | Token::Delimiter(_)
+      | Token::Alternation(_) => (),
+      Token::Override(token, _) => return self.mutate_entry(entry, token, data),
     };
     entry
   }
 }
 
+/// Same semantics as `Regex::captures_iter` but a single set of capture
+/// locations is reused for every match: this saves an allocation per
+/// match on large inputs. Empty matches must move forward by one
+/// character and never directly follow the previous match.
+fn for_each_match<F>(regex: &Regex, input: &str, mut f: F)
+where
+  F: FnMut(&CaptureLocations),
+{
+  let mut locations = regex.capture_locations();
+  let mut start = 0;
+  let mut last_match = None;
+  while start <= input.len() {
+    let (s, e) = match regex.captures_read_at(&mut locations, input, start) {
+      Some(m) => (m.start(), m.end()),
+      None => return,
+    };
+    if s == e {
+      start = e + input[e..].chars().next().map_or(1, char::len_utf8);
+      if last_match == Some(e) {
+        continue;
+      }
+    } else {
+      start = e;
+    }
+    last_match = Some(e);
+    f(&locations);
+  }
+}
+
+/// Shrink the given byte range to whole lines, leaving out those it
+/// shares with the surrounding matches.
+fn untouched_range(input: &str, (start, end): (usize, usize)) -> (usize, usize) {
+  let start = match input[..start].ends_with('\n') || start == 0 {
+    true => start,
+    false => input[start..end].find('\n').map_or(end, |n| start + n + 1),
+  };
+  let end = match end == input.len() {
+    true => end,
+    false => input[start..end]
+      .rfind('\n')
+      .map_or(start, |n| start + n + 1),
+  };
+  (start, end)
+}
+
+/// Wrapped lines of a message are joined back with single spaces.
+fn join_lines(value: &str) -> String {
+  value.lines().map(str::trim).collect::<Vec<_>>().join(" ")
+}
+
+/// Brackets are not part of the code, which may be missing.
+fn unbracket(value: &str) -> Option<&str> {
+  value
+    .strip_prefix('[')
+    .and_then(|value| value.strip_suffix(']'))
+}
+
+/// Surrounding double quotes are not part of the filename.
+fn unquote(value: &str) -> &str {
+  match value.len() {
+    len if len > 1 && value.starts_with('"') && value.ends_with('"') => &value[1..len - 1],
+    _ => value,
+  }
+}
+
+/// Numeric tokens only ever capture digits, so the conversion can only
+/// fail on absurdly large values (think of minified files): saturate
+/// silently instead of crashing.
+fn parse_number(value: &str) -> u32 {
+  value.parse::<u32>().unwrap_or(u32::MAX)
+}
+
 #[cfg(test)]
 mod tests {
   use super::*;
@@ -176,6 +1016,11 @@ mod tests {
     assert_eq!(expected, actual)
   }
 
+  #[test]
+  fn test_run_with_empty_errfmt() {
+    assert_eq!(Err(ErrfmtError::Empty), run("foo", "", ""))
+  }
+
   #[test]
   fn test_parser_should_have_an_entry_if_it_matches() {
     let sut = Parser::new(String::from("Error: %f:%l:%c: %k: %m"), String::new());
@@ -198,7 +1043,7 @@ mod tests {
 
   #[test]
   fn test_multiple_entries_with_single_line_mode() {
-    let input = vec![
+    let input = [
       String::from("/tmp/myfile: error on line 7: invalid syntax"),
       String::from("\n"),
       String::from("/tmp/anotherfile: error on line 7: invalid syntax"),
@@ -215,7 +1060,7 @@ mod tests {
 
   #[test]
   fn test_multi_line_mode() {
-    let input = vec![
+    let input = [
       String::from("/tmp/myfile"),
       String::from("\n"),
       String::from("13:37"),
@@ -229,7 +1074,7 @@ mod tests {
 
   #[test]
   fn test_multiples_entries_with_multi_line_mode() {
-    let input = vec![
+    let input = [
       String::from("/tmp/myfile"),
       String::from("\n"),
       String::from("13:37"),
@@ -267,4 +1112,798 @@ mod tests {
     let entries = sut.parse(String::from("error: syntax error: foo")).unwrap();
     assert_eq!("syntax error: foo", entries[0].message)
   }
+
+  #[test]
+  fn test_separator_splits_multi_line_records() {
+    let input = [
+      String::from("/tmp/myfile"),
+      String::from("\n"),
+      String::from("13:37 syntax error"),
+      String::from("\n"),
+      String::from("/tmp/anotherfile"),
+      String::from("\n"),
+      String::from("4:2 invalid token"),
+    ]
+    .join("");
+    let sut = Parser::new(String::from("%f%.%l:%c %m%$"), String::new());
+    let entries = sut.parse(input).unwrap();
+    assert_eq!(2, entries.len());
+    assert_eq!(
+      "/tmp/anotherfile:4:2: error: invalid token",
+      &entries[1].to_string()
+    )
+  }
+
+  #[test]
+  fn test_separator_must_anchor_to_end_of_line() {
+    let sut = Parser::new(String::from("%f:%l:%c%$"), String::new());
+    let entries = sut
+      .parse(String::from("/tmp/myfile:1:2: trailing\n"))
+      .unwrap();
+    assert_eq!(0, entries.len())
+  }
+
+  #[test]
+  fn test_overflowing_numbers_must_saturate() {
+    let sut = Parser::new(String::from("%f:%l:%c"), String::new());
+    let entries = sut
+      .parse(String::from("/tmp/myfile:1:99999999999"))
+      .unwrap();
+    assert_eq!(u32::MAX, entries[0].column)
+  }
+
+  #[test]
+  fn test_literal_percent_sign_before_filename() {
+    let sut = Parser::new(String::from("%%%f:%l"), String::new());
+    let entries = sut.parse(String::from("%/tmp/myfile:3")).unwrap();
+    assert_eq!("/tmp/myfile:3:1: error: ", &entries[0].to_string())
+  }
+
+  #[test]
+  fn test_trailing_whitespace_is_optional_at_end_of_input() {
+    let input = [
+      String::from("/tmp/myfile: error on line 7: invalid syntax"),
+      String::from("\n"),
+      String::from("/tmp/anotherfile: error on line 7: invalid syntax"),
+    ]
+    .join("");
+    let sut = Parser::new(String::from("%f: %k on line %l: %m%."), String::new());
+    let entries = sut.parse(input).unwrap();
+    assert_eq!(2, entries.len())
+  }
+
+  #[test]
+  fn test_last_rustfmt_error_without_trailing_line_break() {
+    let input = [
+      String::from("error: unexpected close delimiter: `}`"),
+      String::from("\n"),
+      String::from("  --> /tmp/test.rs:85:1"),
+      String::from("\n"),
+      String::from("error: unexpected close delimiter: `)`"),
+      String::from("\n"),
+      String::from("  --> /tmp/test.rs:92:3"),
+    ]
+    .join("");
+    let sut = Parser::new(String::from(RUSTFMT_ERRFMT), String::new());
+    let entries = sut.parse(input).unwrap();
+    assert_eq!(
+      "/tmp/test.rs:92:3: error: unexpected close delimiter: `)`",
+      &entries[1].to_string()
+    )
+  }
+
+  #[test]
+  fn test_every_preset_compiles() {
+    for (_, errfmt) in presets() {
+      assert!(compile(errfmt).is_ok())
+    }
+  }
+
+  #[test]
+  fn test_adjacent_numbers_are_rejected() {
+    let actual = parse(
+      String::from("/tmp/myfile:1337"),
+      String::from("%f:%l%c"),
+      String::new(),
+    );
+    assert_eq!(
+      Err(ErrfmtError::Ambiguous(
+        String::from("%l"),
+        String::from("%c")
+      )),
+      actual.map(|entries| entries.len())
+    )
+  }
+
+  #[test]
+  fn test_unknown_placeholders_are_rejected() {
+    let actual = compile("%f:%l:%x %m").map(|_| ());
+    assert_eq!(
+      Err(ErrfmtError::UnknownPlaceholder(String::from("%x"))),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_regex_override() {
+    let sut = Parser::new(String::from(r"%f(/\S+\.rs/):%l: %m"), String::new());
+    let entries = sut
+      .parse(String::from("foo.c:1: skipped\nsrc/main.rs:2: kept"))
+      .unwrap();
+    assert_eq!(
+      vec![String::from("src/main.rs:2:1: error: kept")],
+      entries.iter().map(Entry::to_string).collect::<Vec<_>>()
+    )
+  }
+
+  #[test]
+  fn test_regex_override_of_kind_with_unknown_word() {
+    let sut = Parser::new(String::from(r"%f:%l:%c: %k(/\w+/): %m"), String::new());
+    let entries = sut
+      .parse(String::from("foo.c:1:2: bogus: hi\nfoo.c:3:4: warning: ho"))
+      .unwrap();
+    assert_eq!(
+      vec![
+        String::from("foo.c:1:2: error: hi"),
+        String::from("foo.c:3:4: warning: ho")
+      ],
+      entries.iter().map(Entry::to_string).collect::<Vec<_>>()
+    )
+  }
+
+  #[test]
+  fn test_regex_override_keeps_static_filename() {
+    let sut = Parser::new(String::from(r"%f(/\w+/):%l: %m"), String::from("a.rs"));
+    let entries = sut.parse(String::from("foo:1: bar")).unwrap();
+    assert_eq!("a.rs", entries[0].file)
+  }
+
+  #[test]
+  fn test_invalid_regex_overrides_are_rejected() {
+    let tests = vec![
+      (r"%f(/[a-z/):%m", "%f(/[a-z/)"),
+      (r"%f(/(\w+)\.rs/):%m", r"%f(/(\w+)\.rs/)"),
+      (r"%f:%l(/\d+", r"%l(/\d+"),
+    ];
+    for (errfmt, value) in tests {
+      match compile(errfmt) {
+        Err(ErrfmtError::InvalidOverride(actual, _)) => assert_eq!(value, actual),
+        other => panic!("{}: {:?}", errfmt, other.map(|r| r.to_string())),
+      }
+    }
+  }
+
+  #[test]
+  fn test_lenient_keeps_unmatched_lines_in_order() {
+    let config = Config::new("%f:%l: %m").file("x.c").lenient(true);
+    let input = "first\n\na.c:1: foo\n  second  \nb.c:2: bar; third\nfourth";
+    let entries = Parser::from_config(&config).parse(input).unwrap();
+    assert_eq!(
+      vec!["first", "foo", "second", "bar; third", "fourth"],
+      entries
+        .iter()
+        .map(|entry| entry.message.as_str())
+        .collect::<Vec<_>>()
+    );
+    assert_eq!(("x.c", 1), (entries[0].file.as_str(), entries[0].line))
+  }
+
+  #[test]
+  fn test_lenient_skips_lines_shared_with_matches() {
+    let config = Config::new("%f:%l: %m;").lenient(true);
+    let entries = Parser::from_config(&config)
+      .parse("a.c:1: foo; trailing\nlone")
+      .unwrap();
+    assert_eq!(
+      vec!["foo", "lone"],
+      entries
+        .iter()
+        .map(|entry| entry.message.as_str())
+        .collect::<Vec<_>>()
+    )
+  }
+
+  #[test]
+  fn test_exclude_compares_ids() {
+    let note = |line| {
+      let mut entry = Entry::new();
+      entry.file = String::from("a.c");
+      entry.line = line;
+      entry.kind = Kind::Note;
+      entry.message = String::from("foo");
+      entry
+    };
+    let known = "a.c:1:1: warning: foo\nunrelated\na.c:3:1: error: foo\n";
+    assert_eq!(
+      Ok(vec![note(2)]),
+      exclude(vec![note(1), note(2), note(3)], known)
+    )
+  }
+
+  #[test]
+  fn test_note_errfmt_reads_what_is_left() {
+    let config = Config::new("%f:%l:%c: %k: %m").notes("%f:%l: %m");
+    let input = "a.c:1:2: warning: foo\na.c:1: declared here\nb.c:3:4: error: bar\n";
+    let (entries, stats) = Parser::from_config(&config)
+      .parse_with_stats(input)
+      .unwrap();
+    assert_eq!(
+      vec![
+        (1, Kind::Warning, "foo"),
+        (1, Kind::Note, "declared here"),
+        (3, Kind::Error, "bar")
+      ],
+      entries
+        .iter()
+        .map(|entry| (entry.line, entry.kind, entry.message.as_str()))
+        .collect::<Vec<_>>()
+    );
+    assert_eq!((3, 0), (stats.entries, stats.unmatched_lines()))
+  }
+
+  #[test]
+  fn test_note_errfmt_forces_the_kind() {
+    let config = Config::new("%f:%l:%c: %k: %m").notes("%f:%l: %k %m");
+    let entries = Parser::from_config(&config)
+      .parse("a.c:1: error oops")
+      .unwrap();
+    assert_eq!(Kind::Note, entries[0].kind)
+  }
+
+  #[test]
+  fn test_note_errfmt_then_lenient() {
+    let config = Config::new("%f:%l:%c: %m")
+      .notes("note: %m")
+      .lenient(true)
+      .default_kind(Kind::Warning);
+    let entries = Parser::from_config(&config)
+      .parse("junk\nnote: foo\na.c:1:2: bar\nmore junk")
+      .unwrap();
+    assert_eq!(
+      vec![
+        (Kind::Warning, "junk"),
+        (Kind::Note, "foo"),
+        (Kind::Warning, "bar"),
+        (Kind::Warning, "more junk")
+      ],
+      entries
+        .iter()
+        .map(|entry| (entry.kind, entry.message.as_str()))
+        .collect::<Vec<_>>()
+    )
+  }
+
+  #[test]
+  fn test_invalid_note_errfmt_is_rejected() {
+    let config = Config::new("%f:%l: %m").notes("%x");
+    assert_eq!(
+      Err(ErrfmtError::UnknownPlaceholder(String::from("%x"))),
+      parse_with("", &config).map(|_| ())
+    )
+  }
+
+  #[test]
+  fn test_too_big_errfmt_is_rejected() {
+    let actual = compile(&"%f:%l:%c: %k: %m%.".repeat(1000)).map(|_| ());
+    assert_eq!(Err(ErrfmtError::TooBig(1024 * 128)), actual)
+  }
+
+  #[test]
+  fn test_size_limit() {
+    let errfmt = "%f:%l:%c: %k: %m%.".repeat(1000);
+    let config = Config::new(&errfmt).size_limit(1024);
+    assert_eq!(
+      Err(ErrfmtError::TooBig(1024)),
+      parse_with("", &config).map(|_| ())
+    );
+    let config = Config::new(&errfmt).size_limit(usize::MAX);
+    assert_eq!(Ok(0), parse_with("", &config).map(|entries| entries.len()))
+  }
+
+  #[test]
+  fn test_error_messages_point_at_the_offending_token() {
+    let actual = ErrfmtError::UnknownPlaceholder(String::from("%x")).to_string();
+    assert_eq!(
+      "unknown placeholder %x: use %% (or \\%) for a literal percent sign",
+      actual
+    )
+  }
+
+  #[test]
+  fn test_column_before_line() {
+    let sut = Parser::new(String::from("%f:%c:%l"), String::new());
+    let entries = sut.parse(String::from("/tmp/myfile:13:37")).unwrap();
+    assert_eq!("/tmp/myfile:37:13: error: ", &entries[0].to_string())
+  }
+
+  #[test]
+  fn test_multiple_entries_on_a_single_line() {
+    let sut = Parser::new(String::from("%f:%l:%c: %k: %m; "), String::new());
+    let entries = sut
+      .parse(String::from("foo.c:1:1: error: a; foo.c:1:5: warning: b"))
+      .unwrap();
+    assert_eq!(2, entries.len());
+    assert_eq!("foo.c:1:1: error: a", &entries[0].to_string());
+    assert_eq!("foo.c:1:5: warning: b", &entries[1].to_string())
+  }
+
+  #[test]
+  fn test_single_entry_with_closing_literal() {
+    let sut = Parser::new(String::from("%f:%l: %m; "), String::new());
+    let entries = sut.parse(String::from("foo.c:1: a; b; ")).unwrap();
+    assert_eq!("a", entries[0].message)
+  }
+
+  #[test]
+  fn test_closing_literal_percent_sign() {
+    let sut = Parser::new(String::from("%f:%l: %m%%"), String::new());
+    let entries = sut.parse(String::from("foo.c:1: 42%")).unwrap();
+    assert_eq!("42", entries[0].message)
+  }
+
+  #[test]
+  fn test_offset_is_captured() {
+    let sut = Parser::new(String::from("%f@%o: %m"), String::new());
+    let entries = sut.parse(String::from("a.json@12: syntax error")).unwrap();
+    assert_eq!(Some(12), entries[0].offset)
+  }
+
+  #[test]
+  fn test_optional_whitespace() {
+    let input = String::from("/tmp/myfile:1: foo\n/tmp/myfile:2:bar\n");
+    let sut = Parser::new(String::from("%f:%l:%~%m"), String::new());
+    let entries = sut.parse(input).unwrap();
+    assert_eq!(2, entries.len());
+    assert_eq!("foo", entries[0].message);
+    assert_eq!("bar", entries[1].message)
+  }
+
+  #[test]
+  fn test_zero_positions_are_clamped() {
+    let actual = run(
+      String::from("/tmp/myfile: error on line 0 column 0: invalid syntax"),
+      String::from("%f: %k on line %l column %c: %m"),
+      String::new(),
+    );
+    assert_eq!(
+      Ok(vec![String::from("/tmp/myfile:1:1: error: invalid syntax")]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_column_range() {
+    let actual = parse(
+      String::from("app.py:12:5-9: F401 unused import"),
+      String::from("%f:%l:%c-%C: %m"),
+      String::new(),
+    )
+    .unwrap();
+    assert_eq!((5, Some(9)), (actual[0].column, actual[0].end_column))
+  }
+
+  #[test]
+  fn test_quoted_filename() {
+    let actual = run(
+      String::from("\"my:weird:file.c\":10:5: error: syntax error"),
+      String::from("%q:%l:%c: %k: %m"),
+      String::new(),
+    );
+    assert_eq!(
+      Ok(vec![String::from(
+        "my:weird:file.c:10:5: error: syntax error"
+      )]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_unquoted_filename_with_quoted_placeholder() {
+    let actual = run(
+      String::from("file.c:10:5: error: syntax error"),
+      String::from("%q:%l:%c: %k: %m"),
+      String::new(),
+    );
+    assert_eq!(
+      Ok(vec![String::from("file.c:10:5: error: syntax error")]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_adjacent_skipping_tokens_are_collapsed() {
+    let tests = vec![
+      ("%*%*%m", "%*%m"),
+      ("%+%+%m", "%+%m"),
+      ("%f%~%~%m", "%f%~%m"),
+      ("%f%~%.%~%m", "%f%.%m"),
+      ("%f%.%.%m", "%f%.%.%m"),
+    ];
+    for (errfmt, expected) in tests {
+      let actual: String = shape(String::from(errfmt))
+        .iter()
+        .map(Token::to_string)
+        .collect();
+      assert_eq!(expected, actual);
+    }
+  }
+
+  #[test]
+  fn test_end_of_line_pins_the_last_field() {
+    let sut = Parser::new(String::from("%f:%l: %m (%k)%$"), String::new());
+    let entries = sut
+      .parse(String::from("a.c:1: foo (bar) (warning)\n"))
+      .unwrap();
+    assert_eq!("a.c:1:1: warning: foo (bar)", &entries[0].to_string())
+  }
+
+  #[test]
+  fn test_end_of_line_rejects_trailing_content() {
+    let input = String::from("a.c:1: foo (warning) trailing\n");
+    let pinned = Parser::new(String::from("%f:%l: %m (%k)%$"), String::new());
+    let loose = Parser::new(String::from("%f:%l: %m (%k)"), String::new());
+    assert_eq!(0, pinned.parse(input.clone()).unwrap().len());
+    assert_eq!(
+      "a.c:1:1: warning: foo",
+      &loose.parse(input).unwrap()[0].to_string()
+    )
+  }
+
+  #[test]
+  fn test_timestamp_prefix_is_skipped() {
+    let actual = run(
+      "2024-01-02T03:04:05Z a.c:1:2: syntax error",
+      "%T %f:%l:%c: %m",
+      "",
+    );
+    assert_eq!(
+      Ok(vec![String::from("a.c:1:2: error: syntax error")]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_ignore_indent() {
+    let input = "  a.c:1: foo\n\tb.c:2: bar";
+    let config = Config::new("%f:%l: %m").ignore_indent(true);
+    assert_eq!(
+      Ok(vec![
+        String::from("a.c:1:1: error: foo"),
+        String::from("b.c:2:1: error: bar")
+      ]),
+      run_with(input, &config)
+    );
+    let entries = parse_with(input, &config).unwrap();
+    assert_eq!(Some((0, 12)), entries[0].span())
+  }
+
+  #[test]
+  fn test_indentation_stays_on_its_line() {
+    let config = Config::new("%f:%l: %m").ignore_indent(true);
+    let (_, stats) = parse_with_stats("\n\n  a.c:1: foo\n", &config).unwrap();
+    assert_eq!(1, stats.matched_lines)
+  }
+
+  #[test]
+  fn test_ignore_indent_of_empty_errfmt() {
+    let config = Config::new("").ignore_indent(true);
+    assert_eq!(Err(ErrfmtError::Empty), parse_with("", &config).map(|_| ()))
+  }
+
+  #[test]
+  fn test_thousands_separators() {
+    let tests = vec![
+      (',', "a.c:1,234:5: foo", "a.c:1234:5: error: foo"),
+      ('.', "a.c:1.234:5: foo", "a.c:1234:5: error: foo"),
+      (',', "a.c:12:1,005: foo", "a.c:12:1005: error: foo"),
+    ];
+    for (separator, input, expected) in tests {
+      let config = Config::new("%f:%l:%c: %m").thousands_separator(separator);
+      assert_eq!(Ok(vec![String::from(expected)]), run_with(input, &config));
+    }
+  }
+
+  #[test]
+  fn test_thousands_separator_between_numbers() {
+    let config = Config::new("%f:%l,%c: %m").thousands_separator(',');
+    assert_eq!(
+      Ok(vec![String::from("a.c:12:5: error: foo")]),
+      run_with("a.c:12,5: foo", &config)
+    )
+  }
+
+  #[test]
+  fn test_counted_line_breaks() {
+    let input = "a.c:1:\nsingle\nb.c:2:\n\ndouble";
+    let messages = |errfmt| {
+      parse(input, errfmt, "")
+        .unwrap()
+        .into_iter()
+        .map(|entry| entry.message)
+        .collect::<Vec<_>>()
+    };
+    assert_eq!(vec!["single", "double"], messages("%f:%l:%.%m"));
+    assert_eq!(vec!["single"], messages("%f:%l:%.{1}%m"));
+    assert_eq!(vec!["double"], messages("%f:%l:%.{2}%m"))
+  }
+
+  #[test]
+  fn test_progress_prefix_is_skipped() {
+    let actual = run("[3/10] a.c:1:2: syntax error", "%P %f:%l:%c: %m", "");
+    assert_eq!(
+      Ok(vec![String::from("a.c:1:2: error: syntax error")]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_entries_carry_their_span() {
+    let entries = parse("a.c:1: foo\nb.c:2: bar", "%f:%l: %m%$", "").unwrap();
+    let spans: Vec<_> = entries.iter().map(Entry::span).collect();
+    assert_eq!(vec![Some((0, 11)), Some((11, 21))], spans)
+  }
+
+  #[test]
+  fn test_alternation() {
+    let actual = run(
+      "a.c:1: fatal error: foo (bar)\na.c:2: error: baz\n",
+      "%f:%l: %(error|fatal error): %m%$",
+      "",
+    );
+    assert_eq!(
+      Ok(vec![
+        String::from("a.c:1:1: error: foo (bar)"),
+        String::from("a.c:2:1: error: baz"),
+      ]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_unclosed_alternation_is_rejected() {
+    assert_eq!(
+      Err(ErrfmtError::UnknownPlaceholder(String::from("%("))),
+      run("", "%f: %(error", "")
+    )
+  }
+
+  #[test]
+  fn test_message_stops_at_trailing_rule() {
+    let actual = run(
+      "a.js:1: 'x' is unused [no-unused-vars]",
+      "%f:%l: %m [%*]",
+      "",
+    );
+    assert_eq!(
+      Ok(vec![String::from("a.js:1:1: error: 'x' is unused")]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_last_message_runs_to_end_of_line() {
+    let actual = run("a.js:1: 'x' is unused [no-unused-vars]", "%f:%l: %m", "");
+    assert_eq!(
+      Ok(vec![String::from(
+        "a.js:1:1: error: 'x' is unused [no-unused-vars]"
+      )]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_line_range() {
+    let actual = parse("a.py:10,15: reformatted", "%f:%l,%L: %m", "").unwrap();
+    assert_eq!((10, Some(15)), (actual[0].line, actual[0].end_line))
+  }
+
+  #[test]
+  fn test_message_before_file() {
+    let actual = run(
+      "unexpected token at a.c:3\nmissing 'at' keyword: x at b.c:4\n",
+      "%m at %f:%l%$",
+      "",
+    );
+    assert_eq!(
+      Ok(vec![
+        String::from("a.c:3:1: error: unexpected token"),
+        String::from("b.c:4:1: error: missing 'at' keyword: x"),
+      ]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_blank_whole_input() {
+    assert!(parse_whole(" \n\n", "").is_empty())
+  }
+
+  #[test]
+  fn test_tab_delimited_fields() {
+    let actual = run(
+      "a.c\t3\t5\tunused variable\tx\nb.c\t4\t1\tsyntax error",
+      "%f%t%l%t%c%t%m",
+      "",
+    );
+    assert_eq!(
+      Ok(vec![
+        String::from("a.c:3:5: error: unused variable\tx"),
+        String::from("b.c:4:1: error: syntax error"),
+      ]),
+      actual
+    )
+  }
+
+  #[test]
+  fn test_multi_word_kinds() {
+    let entries = parse(
+      "a.c:1: fatal error: foo.h: No such file\na.c:2: internal compiler error: Segmentation fault",
+      "%f:%l: %k: %m",
+      "",
+    )
+    .unwrap();
+    assert_eq!(
+      vec![
+        (Kind::Fatal, "foo.h: No such file"),
+        (Kind::Fatal, "Segmentation fault")
+      ],
+      entries
+        .iter()
+        .map(|entry| (entry.kind, entry.message.as_str()))
+        .collect::<Vec<_>>()
+    )
+  }
+
+  #[test]
+  fn test_unknown_kinds_do_not_match() {
+    assert_eq!(
+      Ok(vec![String::from("a.c:2:1: error: bar")]),
+      run("a.c:1: oops: foo\na.c:2: error: bar", "%f:%l: %k: %m", "")
+    )
+  }
+
+  #[test]
+  fn test_backslash_escaped_percent_sign() {
+    assert_eq!(
+      Ok(vec![String::from("a.c:1:1: error: foo")]),
+      run("100%f a.c:1: foo", r"100\%f %f:%l: %m", "")
+    )
+  }
+
+  #[test]
+  fn test_explain() {
+    let expected = concat!(
+      "regex: ([^\\x00\\n]+?)(?::)(\\d+)(?:: )([^\\n]+)\n",
+      "match 1 on line 2: \"a.c:3: oops\"\n",
+      "  %f: \"a.c\"\n",
+      "  %l: \"3\"\n",
+      "  %m: \"oops\"\n",
+      "  => a.c:3:1: error: oops\n",
+    );
+    assert_eq!(
+      Ok(String::from(expected)),
+      explain("make: ***\na.c:3: oops", "%f:%l: %m", "")
+    )
+  }
+
+  #[test]
+  fn test_explain_without_match() {
+    assert_eq!(
+      Ok(String::from("regex: (\\d+)\nno match\n")),
+      explain("foo", "%l", "")
+    )
+  }
+
+  #[test]
+  fn test_numeric_severity() {
+    assert_eq!(
+      Ok(vec![
+        String::from("a.js:1:1: warning: foo"),
+        String::from("a.js:2:1: error: bar"),
+      ]),
+      run(
+        "a.js:1 severity=1 foo\na.js:2 severity=2 bar",
+        "%f:%l severity=%s %m",
+        ""
+      )
+    )
+  }
+
+  #[test]
+  fn test_numeric_severity_is_kept() {
+    let entries = parse("a.js:1 3 foo", "%f:%l %s %m", "").unwrap();
+    assert_eq!(Some(3), entries[0].level)
+  }
+
+  #[test]
+  fn test_continued_message() {
+    assert_eq!(
+      Ok(vec![
+        String::from("a.c:1:1: error: foo bar"),
+        String::from("a.c:2:1: error: baz"),
+      ]),
+      run("a.c:1:\n  foo\n    bar\na.c:2:\n  baz", "%f:%l:%.%M", "")
+    )
+  }
+
+  #[test]
+  fn test_optional_bracketed_code() {
+    let entries = parse("error[E1]: foo\nerror: bar", "%k[%e]: %m", "").unwrap();
+    assert_eq!(
+      (Some("E1"), None),
+      (entries[0].code.as_deref(), entries[1].code.as_deref())
+    )
+  }
+
+  #[test]
+  fn test_bracketed_code_shape() {
+    assert_eq!(
+      r"(\b(?i:internal compiler error|internal error|fatal error|parse error|warning|error|note|fatal|panic|internal)\b|(?:✖|✗|✘|❌|⚠|ℹ)\x{fe0f}?)(\[[a-zA-Z0-9_./:-]+\]|)(?:: )([^\n]+)",
+      compile("%k[%e]: %m").unwrap().to_string()
+    )
+  }
+
+  #[test]
+  fn test_parse_each_in_order() {
+    let mut lines = Vec::new();
+    let sut = Parser::new(String::from("%f:%l: %m"), String::new());
+    sut
+      .parse_each("a:1: foo\nb:2: bar", |entry| lines.push(entry.line))
+      .unwrap();
+    assert_eq!(vec![1, 2], lines)
+  }
+
+  #[test]
+  fn test_loose_whitespace_prefers_adjacent_lines() {
+    let sut = Parser::new(String::from(RUSTC_ERRFMT), String::new()).loose();
+    let actual = sut
+      .parse("error: foo\n --> a.rs:1:2\nwarning: bar\nnoise\n --> b.rs:3:4")
+      .unwrap();
+    assert_eq!(
+      vec![
+        String::from("a.rs:1:2: error: foo"),
+        String::from("b.rs:3:4: warning: bar"),
+      ],
+      actual.iter().map(Entry::to_string).collect::<Vec<_>>()
+    )
+  }
+
+  #[test]
+  fn test_strict_whitespace_does_not_skip_lines() {
+    let sut = Parser::new(String::from(RUSTC_ERRFMT), String::new());
+    assert!(sut
+      .parse("error: foo\nnoise\n --> a.rs:1:2")
+      .unwrap()
+      .is_empty())
+  }
+
+  #[test]
+  fn test_eslint_stylish_symbols() {
+    let input = "✖ 2 problems (1 error, 1 warning)\n⚠️ 1 warning potentially fixable\n✓ done";
+    let actual = parse(input, "%k %m", "").unwrap();
+    assert_eq!(
+      vec![
+        (Kind::Error, "2 problems (1 error, 1 warning)"),
+        (Kind::Warning, "1 warning potentially fixable"),
+      ],
+      actual
+        .iter()
+        .map(|entry| (entry.kind, entry.message.as_str()))
+        .collect::<Vec<_>>()
+    )
+  }
+
+  #[test]
+  fn test_wildcard_stays_on_its_line() {
+    let r = compile("a%*b: %m").unwrap();
+    assert!(r.is_match("a-b: c"));
+    assert!(!r.is_match("a\nb: c"))
+  }
+
+  #[test]
+  fn test_multiline_wildcard_crosses_lines() {
+    let r = compile("a%+b: %m").unwrap();
+    assert!(r.is_match("a-b: c"));
+    assert!(r.is_match("a\n-\nb: c"))
+  }
 }
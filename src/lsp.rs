@@ -0,0 +1,199 @@
+use crate::entry::Entry;
+use crate::entry::Kind;
+use serde_json::Value;
+
+/// Read diagnostics dumped by language server wrappers: either whole
+/// `textDocument/publishDiagnostics` notifications or their parameters
+/// (`uri` and `diagnostics`). Documents may span several lines or be
+/// framed by `Content-Length` headers, so they are looked for anywhere
+/// in the input and whatever lies between them is skipped. Positions
+/// are 0-indexed and count UTF-16 code units: lines and columns start
+/// at 1 once read, and the exclusive end of a range is its last
+/// character. A non-empty static filename replaces every path.
+pub fn parse_lsp(input: impl AsRef<str>, file: impl AsRef<str>) -> Vec<Entry> {
+  documents(input.as_ref())
+    .iter()
+    .map(|document| document.get("params").unwrap_or(document))
+    .filter_map(|params| {
+      let path = match file.as_ref() {
+        "" => path(params.get("uri")?.as_str()?),
+        file => file.to_string(),
+      };
+      let diagnostics = params.get("diagnostics")?.as_array()?;
+      Some((path, diagnostics))
+    })
+    .flat_map(|(path, diagnostics)| {
+      diagnostics.iter().filter_map(move |value| {
+        let mut entry = diagnostic(value)?;
+        entry.file = path.clone();
+        Some(entry)
+      })
+    })
+    .collect()
+}
+
+/// Every JSON object found in the input, in order. A brace that does
+/// not open a valid document is skipped.
+fn documents(mut input: &str) -> Vec<Value> {
+  let mut documents = Vec::new();
+  while let Some(start) = input.find('{') {
+    let mut stream = serde_json::Deserializer::from_str(&input[start..]).into_iter::<Value>();
+    match stream.next() {
+      Some(Ok(document)) => {
+        documents.push(document);
+        input = &input[start + stream.byte_offset()..];
+      }
+      _ => input = &input[start + 1..],
+    }
+  }
+  documents
+}
+
+/// Severities go from 1 (error) to 4 (hint), servers leaving it out
+/// mean an error.
+fn diagnostic(value: &Value) -> Option<Entry> {
+  let range = value.get("range")?;
+  let (line, column) = position(range.get("start")?)?;
+  let (end_line, end_column) = position(range.get("end")?)?;
+  let mut entry = Entry::new();
+  entry.line = line.saturating_add(1);
+  entry.column = column.saturating_add(1);
+  entry.end_line = Some(end_line.saturating_add(1)).filter(|end| *end != entry.line);
+  entry.end_column =
+    Some(end_column).filter(|end| *end >= entry.column || entry.end_line.is_some());
+  entry.kind = match value.get("severity").and_then(Value::as_u64) {
+    None | Some(1) => Kind::Error,
+    Some(2) => Kind::Warning,
+    Some(_) => Kind::Note,
+  };
+  entry.code = match value.get("code") {
+    Some(Value::String(code)) => Some(code.clone()),
+    Some(Value::Number(code)) => Some(code.to_string()),
+    _ => None,
+  };
+  entry.message = value.get("message")?.as_str()?.to_string();
+  Some(entry)
+}
+
+/// Positions beyond `u32` are saturated, like in text input.
+fn position(value: &Value) -> Option<(u32, u32)> {
+  let number = |field| {
+    value
+      .get(field)?
+      .as_u64()
+      .map(|n| n.min(u32::MAX as u64) as u32)
+  };
+  Some((number("line")?, number("character")?))
+}
+
+/// Local paths are given as `file://` URIs, percent-encoded. Drive
+/// letters lose the slash in front of them. Other schemes are kept as
+/// is.
+fn path(uri: &str) -> String {
+  let path = match uri.strip_prefix("file://") {
+    Some(path) => path,
+    None => return uri.to_string(),
+  };
+  let path = match path.as_bytes() {
+    [b'/', drive, b':', ..] if drive.is_ascii_alphabetic() => &path[1..],
+    _ => path,
+  };
+  let mut bytes = Vec::with_capacity(path.len());
+  let mut rest = path.as_bytes();
+  while let Some((&byte, tail)) = rest.split_first() {
+    let decoded = match tail {
+      [high, low, ..] if byte == b'%' => std::str::from_utf8(&[*high, *low])
+        .ok()
+        .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+      _ => None,
+    };
+    match decoded {
+      Some(decoded) => {
+        bytes.push(decoded);
+        rest = &tail[2..];
+      }
+      None => {
+        bytes.push(byte);
+        rest = tail;
+      }
+    }
+  }
+  String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const PARAMS: &str = r#"{"uri":"file:///src/a.py","diagnostics":[{"range":{"start":{"line":2,"character":4},"end":{"line":2,"character":9}},"severity":2,"code":"reportUnusedVariable","source":"Pyright","message":"x is not accessed"}]}"#;
+
+  fn render(input: &str) -> Vec<String> {
+    parse_lsp(input, "").iter().map(Entry::to_string).collect()
+  }
+
+  #[test]
+  fn test_parameters() {
+    assert_eq!(
+      vec!["/src/a.py:3:5: warning: x is not accessed [reportUnusedVariable]"],
+      render(PARAMS)
+    );
+    let entry = &parse_lsp(PARAMS, "")[0];
+    assert_eq!((None, Some(9)), (entry.end_line, entry.end_column))
+  }
+
+  #[test]
+  fn test_framed_notifications() {
+    let notification = format!(
+      r#"{{"jsonrpc":"2.0","method":"textDocument/publishDiagnostics","params":{}}}"#,
+      PARAMS
+    );
+    let input = format!(
+      "Content-Length: {0}\r\n\r\n{1}Content-Length: {0}\r\n\r\n{1}",
+      notification.len(),
+      notification
+    );
+    assert_eq!(2, parse_lsp(input, "").len())
+  }
+
+  #[test]
+  fn test_severities() {
+    let input = r#"{"uri":"file:///a.c","diagnostics":[
+      {"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}},"message":"a"},
+      {"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}},"severity":1,"message":"b"},
+      {"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}},"severity":3,"message":"c"},
+      {"range":{"start":{"line":0,"character":0},"end":{"line":0,"character":1}},"severity":4,"message":"d","code":42}
+    ]}"#;
+    let kinds: Vec<_> = parse_lsp(input, "")
+      .iter()
+      .map(|entry| entry.kind)
+      .collect();
+    assert_eq!(
+      vec![Kind::Error, Kind::Error, Kind::Note, Kind::Note],
+      kinds
+    );
+    assert_eq!(Some(String::from("42")), parse_lsp(input, "")[3].code)
+  }
+
+  #[test]
+  fn test_garbage_is_skipped() {
+    let input = format!("{{ not json\n[]\n{{\"method\":\"exit\"}}\n{}", PARAMS);
+    assert_eq!(1, parse_lsp(input, "").len())
+  }
+
+  #[test]
+  fn test_static_filename() {
+    assert_eq!("b.py", parse_lsp(PARAMS, "b.py")[0].file)
+  }
+
+  #[test]
+  fn test_paths() {
+    assert_eq!(
+      ("/a b/c.py", "C:/src/d.py", "untitled:Untitled-1"),
+      (
+        path("file:///a%20b/c.py").as_str(),
+        path("file:///C:/src/d.py").as_str(),
+        path("untitled:Untitled-1").as_str()
+      )
+    )
+  }
+}
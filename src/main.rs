@@ -2,41 +2,754 @@
 extern crate clap;
 
 use clap::App;
+use clap::AppSettings;
+use clap::ArgMatches;
+use clap::ErrorKind;
+use errfmt::Case;
+use errfmt::Changes;
+use errfmt::ColumnUnit;
+use errfmt::Config;
+use errfmt::Entry;
+use errfmt::Format;
+use errfmt::Kind;
+use errfmt::PathRewrite;
+use errfmt::PresetsFile;
+use errfmt::SeverityMap;
+use errfmt::Template;
+use std::collections::BTreeSet;
+use std::env;
+use std::fs;
 use std::io;
+use std::io::IsTerminal;
 use std::io::Read;
+use std::path::Path;
+use std::process;
+use std::process::Command;
+
+/// Exit codes, from the least to the most severe finding. Failures are
+/// kept apart so that CI can tell a broken run from a bad one.
+const EXIT_WARNINGS: i32 = 1;
+const EXIT_ERRORS: i32 = 2;
+const EXIT_FAILURE: i32 = 3;
+
+/// A listener that is not up yet, or restarting, is given this many
+/// more chances, this long apart (in milliseconds).
+#[cfg(unix)]
+const SOCKET_RETRIES: u32 = 10;
+#[cfg(unix)]
+const SOCKET_DELAY: u64 = 300;
 
 fn main() {
-  invoke_errfmt(parse_args())
-    .map(|output| output.join("\n"))
-    .map(|output| {
+  // Errors are reported once the command line tells whether to do so.
+  let presets = PresetsFile::load();
+  let fallback = PresetsFile::default();
+  let config = load_yaml!("../cli.yml");
+  // Not available from YAML, this lets the command line override defaults.
+  let args = App::from_yaml(config)
+    .setting(AppSettings::AllArgsOverrideSelf)
+    .get_matches_from_safe(presets.as_ref().unwrap_or(&fallback).args(env::args_os()))
+    .unwrap_or_else(|err| usage_error(err));
+  // Flags are global: whatever the subcommand, its matches hold them.
+  let (subcommand, matches) = args.subcommand();
+  let args = matches.unwrap_or(&args);
+  let quiet = args.is_present("quiet");
+  let presets = presets.unwrap_or_else(|err| {
+    if !quiet {
+      eprintln!("{}", err);
+    }
+    fallback
+  });
+  match subcommand {
+    "presets" => list_presets(&presets),
+    _ if args.is_present("list-presets") => list_presets(&presets),
+    "explain" => output(dry_run(args, &presets).map(success), quiet),
+    _ if args.is_present("dry-run") => output(dry_run(args, &presets).map(success), quiet),
+    "detect" => output(detect(args, &presets).map(success), quiet),
+    _ => match args.value_of("output-socket") {
+      Some(path) => output(parse(args, &presets).and_then(|o| send(o, path)), quiet),
+      None => output(parse(args, &presets), quiet),
+    },
+  }
+}
+
+/// Print whatever was produced and exit with the given code, errors make
+/// the program fail.
+fn output(output: Result<(String, i32), String>, quiet: bool) {
+  match output {
+    Ok((output, code)) => {
       if !String::is_empty(&output) {
         println!("{}", output)
       }
+      process::exit(code)
+    }
+    Err(err) => fail(err, quiet),
+  }
+}
+
+/// Editor daemons may listen on a Unix socket (or read a named pipe)
+/// rather than on the standard output. The whole output is sent once
+/// parsing is over. Connecting and writing are attempted again while
+/// the listener is missing or restarting, a daemon that comes back
+/// halfway through gets everything from the start.
+#[cfg(unix)]
+fn send((output, code): (String, i32), path: &str) -> Result<(String, i32), String> {
+  use std::io::ErrorKind;
+  use std::io::Write;
+  use std::os::unix::fs::FileTypeExt;
+  use std::os::unix::net::UnixStream;
+  use std::thread;
+  use std::time::Duration;
+  if String::is_empty(&output) {
+    return Ok((output, code));
+  }
+  let write = |writer: &mut dyn Write| writeln!(writer, "{}", output);
+  let mut retries = 0;
+  loop {
+    let result = match fs::metadata(path) {
+      Ok(metadata) if metadata.file_type().is_fifo() => fs::OpenOptions::new()
+        .write(true)
+        .open(path)
+        .and_then(|mut fifo| write(&mut fifo)),
+      _ => UnixStream::connect(path).and_then(|mut stream| write(&mut stream)),
+    };
+    match result {
+      Ok(()) => return Ok((String::new(), code)),
+      Err(err) if retries < SOCKET_RETRIES => match err.kind() {
+        ErrorKind::NotFound
+        | ErrorKind::ConnectionRefused
+        | ErrorKind::ConnectionReset
+        | ErrorKind::BrokenPipe => {
+          retries += 1;
+          thread::sleep(Duration::from_millis(SOCKET_DELAY))
+        }
+        _ => return Err(format!("cannot write to {}: {}", path, err)),
+      },
+      Err(err) => return Err(format!("cannot write to {}: {}", path, err)),
+    }
+  }
+}
+
+#[cfg(not(unix))]
+fn send(_: (String, i32), path: &str) -> Result<(String, i32), String> {
+  Err(format!(
+    "cannot write to {}: sockets are only supported on Unix",
+    path
+  ))
+}
+
+fn success(output: String) -> (String, i32) {
+  (output, 0)
+}
+
+/// The exit code tells the most severe entry that is reported, unless
+/// the run is merely advisory.
+fn exit_code(entries: &[Entry], args: &ArgMatches) -> i32 {
+  match entries.iter().map(|entry| entry.kind).max() {
+    _ if args.is_present("exit-zero") => 0,
+    None => 0,
+    Some(Kind::Note) | Some(Kind::Warning) => EXIT_WARNINGS,
+    Some(Kind::Error) | Some(Kind::Fatal) => EXIT_ERRORS,
+  }
+}
+
+fn parse(args: &ArgMatches, presets: &PresetsFile) -> Result<(String, i32), String> {
+  invoke_errfmt(args, presets)
+    .and_then(|entries| map_severity(entries, args))
+    .map(|entries| fold_notes(entries, args))
+    .and_then(|entries| rewrite_paths(entries, args))
+    .map(|entries| fallback_file(entries, args))
+    .map(|entries| convert_columns(entries, args))
+    .map(|entries| locate(entries, args))
+    .map(|entries| clamp(entries, args))
+    .map(|entries| changed_only(entries, args))
+    .map(|entries| force_kind(entries, args))
+    .map(|entries| min_severity(entries, args))
+    .map(|entries| trim_message_prefix(entries, args))
+    .map(|entries| squeeze_whitespace(entries, args))
+    .map(|entries| escape_line_breaks(entries, args))
+    .map(|entries| truncate_messages(entries, args))
+    .map(|entries| tag(entries, args))
+    .and_then(|entries| exclude(entries, args))
+    .and_then(|entries| {
+      let output = match template(args)? {
+        _ if args.is_present("only-files") => only_files(&entries),
+        Some(template) => render_template(&entries, &template, kind_case(args)),
+        None if args.is_present("group-by-file") => {
+          errfmt::render_grouped(&entries, kind_case(args))
+        }
+        None => render(
+          &entries,
+          output_format(args),
+          kind_case(args),
+          args.is_present("with-face"),
+        ),
+      };
+      Ok((output, exit_code(&entries, args)))
     })
-    .unwrap_or_else(|err| eprintln!("{}", err))
 }
 
-fn parse_args() -> (String, String) {
-  let config = load_yaml!("../cli.yml");
-  let args = App::from_yaml(config).get_matches();
-  (
-    args
-      .value_of("errfmt")
-      .unwrap_or(errfmt::PASSTHROUGH_ERRFMT)
-      .to_string(),
-    args.value_of("file").unwrap_or("").to_string(),
+fn fail(err: String, quiet: bool) -> ! {
+  if !quiet {
+    eprintln!("{}", err);
+  }
+  process::exit(EXIT_FAILURE)
+}
+
+/// Help and version requests are not errors, wrong usage is a failure.
+fn usage_error(err: clap::Error) -> ! {
+  match err.kind {
+    ErrorKind::HelpDisplayed | ErrorKind::VersionDisplayed => err.exit(),
+    _ => {
+      eprintln!("{}", err.message);
+      process::exit(EXIT_FAILURE)
+    }
+  }
+}
+
+fn invoke_errfmt(args: &ArgMatches, presets: &PresetsFile) -> Result<Vec<Entry>, String> {
+  let errfmt = errfmt(args, presets)?;
+  let whole = args.is_present("whole");
+  let json = args.is_present("cargo-json") || args.value_of("input-format") == Some("json");
+  let lsp = args.value_of("input-format") == Some("lsp");
+  let diff = args.value_of("input-format") == Some("diff");
+  let default_kind = if json || lsp || diff || errfmt::captures_kind(&errfmt) && !whole {
+    None
+  } else {
+    default_kind(args)
+  };
+  let file = static_file(args);
+  inputs(args)?
+    .into_iter()
+    .map(|(name, lines)| (name, strip_ansi(lines, args)))
+    .map(|(name, lines)| (name, skip_noise(lines, args)))
+    .map(|(name, lines)| (name, expand_headings(lines, args)))
+    .map(|(name, lines)| {
+      if whole {
+        let mut entries = errfmt::parse_whole(lines, &file);
+        if let Some(kind) = default_kind {
+          entries.iter_mut().for_each(|entry| entry.kind = kind);
+        }
+        return Ok(entries);
+      }
+      if json {
+        return Ok(errfmt::parse_json(lines, &file));
+      }
+      if lsp {
+        return Ok(errfmt::parse_lsp(lines, &file));
+      }
+      if diff {
+        return Ok(errfmt::parse_diff(lines, &file));
+      }
+      errfmt::parse_with_stats(lines, &config(args, &errfmt))
+        .map(|(entries, stats)| {
+          if args.is_present("verbose") {
+            match name {
+              Some(name) => eprintln!("{}: {}", name, stats),
+              None => eprintln!("{}", stats),
+            }
+          }
+          if args.is_present("profile") {
+            match name {
+              Some(name) => eprintln!("{}: {}", name, stats.profile()),
+              None => eprintln!("{}", stats.profile()),
+            }
+          }
+          entries
+        })
+        .map_err(|err| err.to_string())
+    })
+    .collect::<Result<Vec<_>, _>>()
+    .map(|batches| batches.into_iter().flatten().collect::<Vec<_>>())
+}
+
+/// Options of text input, shared by parsing and explaining so that a dry
+/// run reads the input the way a real one does.
+fn config(args: &ArgMatches, errfmt: &str) -> Config {
+  let default_kind = match errfmt::captures_kind(errfmt) {
+    true => None,
+    false => default_kind(args),
+  };
+  let mut config = Config::new(errfmt)
+    .file(static_file(args))
+    .loose(args.is_present("loose-multiline"))
+    .lenient(args.is_present("lenient"))
+    .ignore_indent(args.is_present("ignore-indent"))
+    .default_kind(default_kind.unwrap_or(Kind::Error));
+  if let Some(notes) = args.value_of("note-errfmt") {
+    config = config.notes(notes);
+  }
+  if args.is_present("no-size-limit") {
+    config = config.size_limit(usize::MAX);
+  }
+  match args.value_of("numeric-locale") {
+    Some("comma") => config.thousands_separator(','),
+    Some("dot") => config.thousands_separator('.'),
+    _ => config,
+  }
+}
+
+/// Search results are not diagnostics: unless told otherwise, matches
+/// read with a search preset are notes.
+fn default_kind(args: &ArgMatches) -> Option<Kind> {
+  let search = !args.is_present("errfmt")
+    && !args.is_present("errfmt-file")
+    && matches!(args.value_of("preset"), Some("grep") | Some("ripgrep"));
+  match args.value_of("default-kind") {
+    None if search => Some(Kind::Note),
+    kind => kind.and_then(|kind| kind.parse().ok()),
+  }
+}
+
+/// Each input is explained on its own, named ones under a header.
+fn dry_run(args: &ArgMatches, presets: &PresetsFile) -> Result<String, String> {
+  let config = config(args, &errfmt(args, presets)?);
+  inputs(args)?
+    .into_iter()
+    .map(|(name, lines)| {
+      let lines = expand_headings(skip_noise(strip_ansi(lines, args), args), args);
+      errfmt::explain_with(lines, &config)
+        .map(|report| match name {
+          Some(name) => format!("==> {} <==\n{}", name, report),
+          None => report,
+        })
+        .map_err(|err| err.to_string())
+    })
+    .collect::<Result<Vec<_>, _>>()
+    .map(|reports| reports.join("\n").trim_end().to_string())
+}
+
+/// Every preset is tried against the whole input, the ones matching
+/// anything are listed from the best coverage to the worst along with
+/// how many entries they found.
+fn detect(args: &ArgMatches, presets: &PresetsFile) -> Result<String, String> {
+  let lines = inputs(args)?
+    .into_iter()
+    .map(|(_, lines)| expand_headings(skip_noise(strip_ansi(lines, args), args), args))
+    .collect::<Vec<_>>()
+    .join("\n");
+  let mut ranking = presets
+    .presets()
+    .into_iter()
+    .filter_map(|(name, errfmt)| {
+      errfmt::parse_with_stats(&lines, &errfmt::Config::new(errfmt))
+        .ok()
+        .filter(|(_, stats)| stats.entries > 0)
+        .map(|(_, stats)| (name, stats))
+    })
+    .collect::<Vec<_>>();
+  if ranking.is_empty() {
+    return Err(String::from("no preset matches the input"));
+  }
+  ranking.sort_by(|(_, a), (_, b)| b.coverage().total_cmp(&a.coverage()));
+  Ok(
+    ranking
+      .iter()
+      .map(|(name, stats)| format!("{}\t{:.2}\t{}", name, stats.coverage(), stats.entries))
+      .collect::<Vec<_>>()
+      .join("\n"),
   )
 }
 
-fn invoke_errfmt((errfmt, file): (String, String)) -> Result<Vec<String>, String> {
-  stdin_lines().and_then(move |lines| errfmt::run(lines, errfmt, file))
+/// In fallback mode, the static filename is applied after parsing.
+fn static_file(args: &ArgMatches) -> String {
+  match args.value_of("file-mode") {
+    Some("fallback") => String::new(),
+    _ => args.value_of("file").unwrap_or("").to_string(),
+  }
+}
+
+/// An explicit errorformat string wins over a file, which wins over a
+/// preset.
+fn errfmt(args: &ArgMatches, presets: &PresetsFile) -> Result<String, String> {
+  match (
+    args.value_of("errfmt"),
+    args.value_of("errfmt-file"),
+    args.value_of("preset"),
+  ) {
+    (Some(errfmt), _, _) => Ok(errfmt.to_string()),
+    (None, Some(path), _) => read_errfmt(path),
+    (None, None, Some(name)) => presets
+      .preset(name)
+      .ok_or_else(|| format!("unknown preset: {}", name)),
+    (None, None, None) => Ok(errfmt::PASSTHROUGH_ERRFMT.to_string()),
+  }
+}
+
+/// Editors usually end files with a line break, which is not part of
+/// the errorformat string. Any other whitespace is.
+fn read_errfmt(path: &str) -> Result<String, String> {
+  let errfmt = fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+  let errfmt = errfmt.strip_suffix('\n').unwrap_or(&errfmt);
+  Ok(errfmt.strip_suffix('\r').unwrap_or(errfmt).to_string())
 }
 
-fn stdin_lines() -> Result<String, String> {
-  let mut lines = String::new();
+fn list_presets(presets: &PresetsFile) {
+  presets
+    .presets()
+    .iter()
+    .for_each(|(name, errfmt)| println!("{}\t{}", name, errfmt))
+}
+
+/// Some tools insist on emitting colors, even through a pipe.
+fn strip_ansi(lines: String, args: &ArgMatches) -> String {
+  if args.is_present("strip-ansi") {
+    errfmt::strip_ansi(lines)
+  } else {
+    lines
+  }
+}
+
+/// C and C++ compilers surround diagnostics with context that may
+/// look like a diagnostic itself.
+fn skip_noise(lines: String, args: &ArgMatches) -> String {
+  if args.is_present("skip-noise") {
+    errfmt::skip_noise(lines)
+  } else {
+    lines
+  }
+}
+
+fn expand_headings(lines: String, args: &ArgMatches) -> String {
+  if args.is_present("expand-headings") {
+    errfmt::expand_headings(lines)
+  } else {
+    lines
+  }
+}
+
+/// Numeric severities were read with the default thresholds, only
+/// entries that have one are affected. Notes must be known before they
+/// are folded.
+fn map_severity(mut entries: Vec<Entry>, args: &ArgMatches) -> Result<Vec<Entry>, String> {
+  if let Some(map) = args.value_of("severity-map") {
+    let map: SeverityMap = map.parse()?;
+    entries
+      .iter_mut()
+      .for_each(|entry| entry.kind = entry.level.map_or(entry.kind, |level| map.kind(level)));
+  }
+  Ok(entries)
+}
+
+/// Must run before kinds are overridden since it relies on notes.
+fn fold_notes(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  if args.is_present("fold-notes") {
+    errfmt::fold_notes(entries)
+  } else {
+    entries
+  }
+}
+
+/// Captured paths are sent back to the real sources before anything
+/// reads them. Rules apply in order.
+fn rewrite_paths(mut entries: Vec<Entry>, args: &ArgMatches) -> Result<Vec<Entry>, String> {
+  let rules = match args.values_of("path-rewrite") {
+    Some(rules) => rules
+      .map(str::parse)
+      .collect::<Result<Vec<PathRewrite>, _>>()?,
+    None => return Ok(entries),
+  };
+  entries.iter_mut().for_each(|entry| {
+    entry.file = rules
+      .iter()
+      .fold(entry.file.clone(), |file, rule| rule.apply(&file))
+  });
+  Ok(entries)
+}
+
+/// Entries are matched against the lines added since the given revision,
+/// once their file and position are final. Outside of a git repository,
+/// nothing is filtered out. Paths are resolved on disk before being
+/// compared with those of the diff, which are relative to the root of
+/// the repository: a file that no longer exists has no added line.
+fn changed_only(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  if !args.is_present("changed-only") {
+    return entries;
+  }
+  let since = args.value_of("since").unwrap_or("HEAD");
+  let (root, diff, cwd) = match (
+    git(&["rev-parse", "--show-toplevel"]),
+    git(&[
+      "diff",
+      "-U0",
+      "--no-color",
+      "--no-ext-diff",
+      "--end-of-options",
+      since,
+    ]),
+    env::current_dir(),
+  ) {
+    (Some(root), Some(diff), Ok(cwd)) => (root, diff, cwd),
+    _ => return entries,
+  };
+  let changes = Changes::parse(&diff);
+  let root = match Path::new(root.trim_end()).canonicalize() {
+    Ok(root) => root,
+    Err(_) => return entries,
+  };
+  entries
+    .into_iter()
+    .filter(|entry| {
+      cwd.join(&entry.file).canonicalize().is_ok_and(|file| {
+        file
+          .strip_prefix(&root)
+          .is_ok_and(|file| changes.contains(file, entry.line))
+      })
+    })
+    .collect()
+}
+
+/// Output of a successful git command.
+fn git(args: &[&str]) -> Option<String> {
+  Command::new("git")
+    .args(args)
+    .output()
+    .ok()
+    .filter(|output| output.status.success())
+    .and_then(|output| String::from_utf8(output.stdout).ok())
+}
+
+/// In fallback mode, the static filename is only used for entries
+/// where the tool did not report any path.
+fn fallback_file(mut entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  if let (Some("fallback"), Some(file)) = (args.value_of("file-mode"), args.value_of("file")) {
+    entries
+      .iter_mut()
+      .filter(|entry| String::is_empty(&entry.file))
+      .for_each(|entry| entry.file = file.to_string());
+  }
+  entries
+}
+
+/// Columns can only be translated with the static file at hand. This
+/// must happen before offsets are located as those are already bytes.
+fn convert_columns(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  let unit = match args.value_of("column-unit") {
+    Some("codepoint") => ColumnUnit::Codepoint,
+    Some("grapheme") => ColumnUnit::Grapheme,
+    _ => return entries,
+  };
+  let source = args
+    .value_of("file")
+    .and_then(|file| fs::read_to_string(file).ok());
+  entries
+    .into_iter()
+    .map(|entry| entry.convert_columns(unit, source.as_deref()))
+    .collect()
+}
+
+/// Byte offsets can only be translated with the static file at hand.
+fn locate(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  let source = match entries.iter().any(|entry| entry.offset.is_some()) {
+    true => args
+      .value_of("file")
+      .and_then(|file| fs::read_to_string(file).ok()),
+    false => None,
+  };
+  entries
+    .into_iter()
+    .map(|entry| entry.locate(source.as_deref()))
+    .collect()
+}
+
+/// Raw numbers are kept until now so that offsets can be translated.
+fn clamp(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  let min = value_t!(args, "min-position", u32).unwrap_or_else(|err| usage_error(err));
+  entries.into_iter().map(|entry| entry.clamp(min)).collect()
+}
+
+/// Override whatever kind was extracted (or defaulted) for every entry.
+fn force_kind(mut entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  if let Some(kind) = args
+    .value_of("force-kind")
+    .and_then(|kind| kind.parse().ok())
+  {
+    entries.iter_mut().for_each(|entry| entry.kind = kind);
+  }
+  entries
+}
+
+/// Only the final kind counts, notes may already have been folded.
+fn min_severity(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  let min = match args.value_of("min-severity") {
+    Some("info") => Kind::Note,
+    Some(kind) => kind.parse().unwrap_or(Kind::Error),
+    None => return entries,
+  };
+  entries
+    .into_iter()
+    .filter(|entry| entry.kind >= min)
+    .collect()
+}
+
+/// Must run once the kind is final since only the rendered kind is
+/// removed from the message.
+fn trim_message_prefix(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  if args.is_present("trim-message-prefix") {
+    entries.into_iter().map(Entry::trim_kind_prefix).collect()
+  } else {
+    entries
+  }
+}
+
+/// Runs before truncation so that padding does not eat the budget.
+fn squeeze_whitespace(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  if args.is_present("squeeze-whitespace") {
+    entries.into_iter().map(Entry::squeeze_whitespace).collect()
+  } else {
+    entries
+  }
+}
+
+/// JSON escapes line breaks on its own, any other output has one entry
+/// per line.
+fn escape_line_breaks(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  if output_format(args) == Format::Jsonl && !args.is_present("template") {
+    return entries;
+  }
+  let marker = args.value_of("newline-repr").unwrap_or_default();
+  entries
+    .into_iter()
+    .map(|entry| entry.escape_line_breaks(marker))
+    .collect()
+}
+
+/// Last transformation: the message is final once trimmed.
+fn truncate_messages(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  if !args.is_present("max-message-len") {
+    return entries;
+  }
+  let max = value_t!(args, "max-message-len", usize).unwrap_or_else(|err| usage_error(err));
+  entries
+    .into_iter()
+    .map(|entry| entry.truncate_message(max))
+    .collect()
+}
+
+/// Tags are never truncated.
+fn tag(entries: Vec<Entry>, args: &ArgMatches) -> Vec<Entry> {
+  match args.value_of("tag") {
+    Some(name) => entries.into_iter().map(|entry| entry.tag(name)).collect(),
+    None => entries,
+  }
+}
+
+/// Entries are compared once final, as they were printed before. Only
+/// the default output format can be read back.
+fn exclude(entries: Vec<Entry>, args: &ArgMatches) -> Result<Vec<Entry>, String> {
+  match args.value_of("exclude-file") {
+    Some(_) if output_format(args) != Format::Kak => Err(format!(
+      "--exclude-file cannot read back entries printed with --format {}",
+      args.value_of("format").unwrap_or("kak")
+    )),
+    Some(path) => fs::read_to_string(path)
+      .map_err(|err| format!("{}: {}", path, err))
+      .and_then(|known| errfmt::exclude(entries, known).map_err(|err| err.to_string())),
+    None => Ok(entries),
+  }
+}
+
+fn output_format(args: &ArgMatches) -> Format {
+  match args.value_of("format") {
+    _ if args.is_present("compact") => Format::Compact,
+    Some("compact") => Format::Compact,
+    Some("grep") => Format::Grep,
+    Some("jsonl") => Format::Jsonl,
+    Some("kak-range") => Format::KakRange,
+    _ => Format::Kak,
+  }
+}
+
+fn kind_case(args: &ArgMatches) -> Case {
+  match args.value_of("kind-case") {
+    Some("upper") => Case::Upper,
+    Some("title") => Case::Title,
+    _ => Case::Lower,
+  }
+}
+
+/// Each file is printed once, handy to feed another command.
+fn only_files(entries: &[Entry]) -> String {
+  entries
+    .iter()
+    .map(|entry| entry.file.as_str())
+    .collect::<BTreeSet<_>>()
+    .into_iter()
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+fn template(args: &ArgMatches) -> Result<Option<Template>, String> {
+  args
+    .value_of("template")
+    .map(|template| {
+      template
+        .parse()
+        .map_err(|err: errfmt::ErrfmtError| err.to_string())
+    })
+    .transpose()
+}
+
+fn render_template(entries: &[Entry], template: &Template, case: Case) -> String {
+  entries
+    .iter()
+    .map(|entry| template.render(entry, case))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Faces only make sense to Kakoune, other formats are left alone.
+fn render(entries: &[Entry], format: Format, case: Case, face: bool) -> String {
+  entries
+    .iter()
+    .map(|entry| match format {
+      Format::Kak | Format::KakRange if face => {
+        format!("{}\t{}", entry.render(format, case), entry.kind.face())
+      }
+      format => entry.render(format, case),
+    })
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+/// Each input is parsed on its own so that no record spans two of
+/// them. Only named inputs are labelled in verbose reports. Waiting for
+/// a terminal to type diagnostics would look like a hang: only an
+/// explicit `-` reads stdin from it.
+fn inputs<'a>(args: &'a ArgMatches) -> Result<Vec<(Option<&'a str>, String)>, String> {
+  match args.values_of("inputs") {
+    Some(paths) => paths
+      .map(|path| match path {
+        "-" => stdin_lines(args).map(|lines| (Some(path), lines)),
+        path => read_lines(path, args).map(|lines| (Some(path), lines)),
+      })
+      .collect(),
+    None if io::stdin().is_terminal() => Err(String::from(
+      "no input: pipe the output of a tool (e.g. php -l myfile.php | errfmt -p php) or give files",
+    )),
+    None => stdin_lines(args).map(|lines| vec![(None, lines)]),
+  }
+}
+
+fn stdin_lines(args: &ArgMatches) -> Result<String, String> {
+  let mut bytes = Vec::new();
   io::stdin()
     .lock()
-    .read_to_string(&mut lines)
+    .read_to_end(&mut bytes)
     .map_err(|err| err.to_string())?;
-  Ok(lines)
+  decode(bytes, args)
+}
+
+fn read_lines(path: &str, args: &ArgMatches) -> Result<String, String> {
+  let bytes = fs::read(path).map_err(|err| format!("{}: {}", path, err))?;
+  decode(bytes, args).map_err(|err| format!("{}: {}", path, err))
+}
+
+/// Legacy toolchains may not speak UTF-8. Every byte is a valid Latin-1
+/// character, invalid UTF-8 is only tolerated when asked to.
+fn decode(bytes: Vec<u8>, args: &ArgMatches) -> Result<String, String> {
+  match args.value_of("input-encoding") {
+    Some("latin1") => Ok(bytes.into_iter().map(char::from).collect()),
+    _ if args.is_present("lossy") => Ok(String::from_utf8_lossy(&bytes).into_owned()),
+    _ => String::from_utf8(bytes).map_err(|err| format!("invalid input: {} (see --lossy)", err)),
+  }
 }
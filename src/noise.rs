@@ -0,0 +1,66 @@
+use regex::Regex;
+
+/// Remove compiler chatter that surrounds actual diagnostics and could
+/// be mistaken for one: include stacks, function context, source echo
+/// with its caret line and final summaries (gcc and clang flavors).
+pub fn skip_noise(input: String) -> String {
+  lazy_static! {
+    static ref RE: Regex = Regex::new(concat!(
+      r"(?m)^(?:",
+      r"In file included from [^\n]*",
+      r"|[ \t]+from [^\n]*[:,]",
+      r"|[^\n]*: (?:In|At) [^\n]*:",
+      r"|[ \t]*\d*[ \t]*\|[^\n]*",
+      r"|[ \t]*[\^~][\^~ \t]*",
+      r"|\d+ (?:warning|error)s? (?:and \d+ (?:warning|error)s? )?generated\.",
+      r")\r?(?:\n|\z)",
+    ))
+    .unwrap();
+  }
+  RE.replace_all(&input, "").into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_diagnostics_are_untouched() {
+    let input = String::from("/tmp/foo.c:1:2: error: syntax error");
+    assert_eq!(input.clone(), skip_noise(input))
+  }
+
+  #[test]
+  fn test_gcc_noise() {
+    let input = String::from(concat!(
+      "In file included from /tmp/foo.h:1,\n",
+      "                 from /tmp/foo.c:2:\n",
+      "/tmp/bar.h: In function 'main':\n",
+      "/tmp/bar.h:3:5: error: expected ';' before 'return'\n",
+      "    3 |   int x = 1\n",
+      "      |            ^\n",
+      "      |            ;\n",
+      "/tmp/bar.h:4:3: warning: unused variable 'y'\n",
+    ));
+    let expected = String::from(concat!(
+      "/tmp/bar.h:3:5: error: expected ';' before 'return'\n",
+      "/tmp/bar.h:4:3: warning: unused variable 'y'\n",
+    ));
+    assert_eq!(expected, skip_noise(input))
+  }
+
+  #[test]
+  fn test_clang_noise() {
+    let input = String::from(concat!(
+      "/tmp/foo.c:3:12: warning: unused parameter 'argc'\n",
+      "int main(int argc) {\n",
+      "           ^~~~\n",
+      "1 warning generated.",
+    ));
+    let expected = String::from(concat!(
+      "/tmp/foo.c:3:12: warning: unused parameter 'argc'\n",
+      "int main(int argc) {\n",
+    ));
+    assert_eq!(expected, skip_noise(input))
+  }
+}
@@ -0,0 +1,173 @@
+use std::env;
+use std::ffi::OsString;
+use std::fs;
+use std::path::PathBuf;
+use toml::Table;
+use toml::Value;
+
+/// User-defined presets and default flags, read from
+/// `$XDG_CONFIG_HOME/errfmt/presets.toml` (`~/.config` being the
+/// default configuration directory):
+///
+/// ```toml
+/// [presets]
+/// mypy = "%f:%l: %k: %m"
+///
+/// [defaults]
+/// preset = "mypy"
+/// strip-ansi = true
+/// ```
+///
+/// User presets take precedence over built-in ones. Defaults behave as
+/// if they were written first on the command line.
+#[derive(Debug, Default)]
+pub struct PresetsFile {
+  presets: Vec<(String, String)>,
+  defaults: Vec<String>,
+}
+
+impl PresetsFile {
+  /// A missing file is not an error: there is simply nothing to add.
+  pub fn load() -> Result<Self, String> {
+    match Self::path().filter(|path| path.is_file()) {
+      Some(path) => fs::read_to_string(&path)
+        .map_err(|err| err.to_string())
+        .and_then(|content| Self::parse(&content))
+        .map_err(|err| format!("{}: {}", path.display(), err)),
+      None => Ok(Self::default()),
+    }
+  }
+
+  fn path() -> Option<PathBuf> {
+    env::var_os("XDG_CONFIG_HOME")
+      .map(PathBuf::from)
+      .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+      .map(|dir| dir.join("errfmt").join("presets.toml"))
+  }
+
+  pub fn parse(content: &str) -> Result<Self, String> {
+    let table = content.parse::<Table>().map_err(|err| err.to_string())?;
+    Ok(Self {
+      presets: section(&table, "presets")?
+        .iter()
+        .map(|(name, value)| match value {
+          Value::String(errfmt) => Ok((name.clone(), errfmt.clone())),
+          _ => Err(format!("preset {} must be a string", name)),
+        })
+        .collect::<Result<_, _>>()?,
+      defaults: section(&table, "defaults")?
+        .iter()
+        .map(|(name, value)| match value {
+          Value::Boolean(true) => Ok(vec![format!("--{}", name)]),
+          Value::Boolean(false) => Ok(vec![]),
+          Value::String(value) => Ok(vec![format!("--{}", name), value.clone()]),
+          Value::Integer(value) => Ok(vec![format!("--{}", name), value.to_string()]),
+          _ => Err(format!(
+            "default {} must be a boolean, a string or a number",
+            name
+          )),
+        })
+        .collect::<Result<Vec<_>, _>>()?
+        .concat(),
+    })
+  }
+
+  /// Look up a preset, user-defined ones first.
+  pub fn preset(&self, name: &str) -> Option<String> {
+    self
+      .presets
+      .iter()
+      .find(|(n, _)| n == name)
+      .map(|(_, errfmt)| errfmt.clone())
+      .or_else(|| crate::preset(name).map(String::from))
+  }
+
+  /// Every available preset, sorted by name.
+  pub fn presets(&self) -> Vec<(String, String)> {
+    let mut presets: Vec<(String, String)> = crate::presets()
+      .iter()
+      .filter(|(name, _)| self.presets.iter().all(|(n, _)| n != name))
+      .map(|(name, errfmt)| (name.to_string(), errfmt.to_string()))
+      .chain(self.presets.iter().cloned())
+      .collect();
+    presets.sort();
+    presets
+  }
+
+  /// Insert default flags right after the program name.
+  pub fn args<I>(&self, argv: I) -> Vec<OsString>
+  where
+    I: IntoIterator<Item = OsString>,
+  {
+    let mut argv = argv.into_iter();
+    argv
+      .next()
+      .into_iter()
+      .chain(self.defaults.iter().map(OsString::from))
+      .chain(argv)
+      .collect()
+  }
+}
+
+/// Sections are optional but must be tables.
+fn section<'a>(table: &'a Table, name: &str) -> Result<&'a Table, String> {
+  lazy_static! {
+    static ref EMPTY: Table = Table::new();
+  }
+  match table.get(name) {
+    Some(Value::Table(section)) => Ok(section),
+    Some(_) => Err(format!("{} must be a table", name)),
+    None => Ok(&EMPTY),
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_user_preset() {
+    let sut = PresetsFile::parse("[presets]\nmypy = \"%f:%l: %k: %m\"").unwrap();
+    assert_eq!(Some(String::from("%f:%l: %k: %m")), sut.preset("mypy"))
+  }
+
+  #[test]
+  fn test_builtin_presets_are_merged() {
+    let sut = PresetsFile::parse("[presets]\nmypy = \"%f:%l: %k: %m\"").unwrap();
+    assert_eq!(Some(String::from(crate::PHP_ERRFMT)), sut.preset("php"));
+    assert_eq!(crate::presets().len() + 1, sut.presets().len())
+  }
+
+  #[test]
+  fn test_user_presets_override_builtin_ones() {
+    let sut = PresetsFile::parse("[presets]\nphp = \"%m\"").unwrap();
+    assert_eq!(Some(String::from("%m")), sut.preset("php"));
+    assert_eq!(crate::presets().len(), sut.presets().len())
+  }
+
+  #[test]
+  fn test_defaults_come_before_arguments() {
+    let sut = PresetsFile::parse("[defaults]\npreset = \"php\"\nstrip-ansi = true").unwrap();
+    let argv = vec![
+      OsString::from("errfmt"),
+      OsString::from("-f"),
+      OsString::from("foo"),
+    ];
+    let expected: Vec<OsString> = vec!["errfmt", "--preset", "php", "--strip-ansi", "-f", "foo"]
+      .into_iter()
+      .map(OsString::from)
+      .collect();
+    assert_eq!(expected, sut.args(argv))
+  }
+
+  #[test]
+  fn test_invalid_preset() {
+    assert!(PresetsFile::parse("[presets]\nphp = 42").is_err())
+  }
+
+  #[test]
+  fn test_empty_file() {
+    let sut = PresetsFile::parse("").unwrap();
+    assert_eq!(crate::presets().len(), sut.presets().len())
+  }
+}
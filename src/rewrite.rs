@@ -0,0 +1,90 @@
+use regex::Regex;
+use std::str::FromStr;
+
+/// A sed-like substitution applied to captured paths, e.g. to send
+/// generated code back to its source: `s|/build/gen/|/src/|`. Any
+/// character can delimit the parts, a trailing `g` replaces every
+/// occurrence instead of the first one only. The replacement refers to
+/// capture groups as `$1` or `${name}`.
+#[derive(Debug, Clone)]
+pub struct PathRewrite {
+  regex: Regex,
+  replacement: String,
+  global: bool,
+}
+
+impl PathRewrite {
+  pub fn apply(&self, path: &str) -> String {
+    match self.global {
+      true => self.regex.replace_all(path, self.replacement.as_str()),
+      false => self.regex.replace(path, self.replacement.as_str()),
+    }
+    .into_owned()
+  }
+}
+
+impl FromStr for PathRewrite {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    let invalid = || format!("invalid path rewrite: {}", value);
+    let delimiter = value
+      .strip_prefix('s')
+      .and_then(|rest| rest.chars().next())
+      .filter(|c| !c.is_alphanumeric() && !c.is_whitespace())
+      .ok_or_else(invalid)?;
+    let parts: Vec<&str> = value[1 + delimiter.len_utf8()..].split(delimiter).collect();
+    let (find, replacement, global) = match parts.as_slice() {
+      [find, replacement, ""] => (find, replacement, false),
+      [find, replacement, "g"] => (find, replacement, true),
+      _ => return Err(invalid()),
+    };
+    Ok(PathRewrite {
+      regex: Regex::new(find).map_err(|err| format!("{}: {}", invalid(), err))?,
+      replacement: replacement.to_string(),
+      global,
+    })
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn rewrite(rule: &str, path: &str) -> String {
+    rule.parse::<PathRewrite>().unwrap().apply(path)
+  }
+
+  #[test]
+  fn test_prefix_rewrite() {
+    assert_eq!(
+      "/src/foo.rs",
+      rewrite("s|/build/gen/|/src/|", "/build/gen/foo.rs")
+    )
+  }
+
+  #[test]
+  fn test_capture_reuse() {
+    assert_eq!(
+      "src/foo.rs",
+      rewrite(r"s#^/sandbox/[^/]+/(.*)$#src/$1#", "/sandbox/1234/foo.rs")
+    )
+  }
+
+  #[test]
+  fn test_first_occurrence_only() {
+    assert_eq!("b/a/c", rewrite("s/a/b/", "a/a/c"))
+  }
+
+  #[test]
+  fn test_every_occurrence() {
+    assert_eq!("b/b/c", rewrite("s/a/b/g", "a/a/c"))
+  }
+
+  #[test]
+  fn test_invalid_rules() {
+    for rule in &["", "s", "s|a|b", "s|a|b|x", "x|a|b|", "sa|b|c|", "s|(|b|"] {
+      assert!(rule.parse::<PathRewrite>().is_err(), "{}", rule)
+    }
+  }
+}
@@ -0,0 +1,97 @@
+use crate::entry::Kind;
+use std::str::FromStr;
+
+/// Numeric severity levels, as reported by some linters instead of
+/// words, translate to kinds through thresholds: a level gets the kind
+/// of the highest threshold it reaches. Levels below every threshold
+/// are notes.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SeverityMap {
+  thresholds: Vec<(u32, Kind)>,
+}
+
+impl SeverityMap {
+  pub fn kind(&self, level: u32) -> Kind {
+    self
+      .thresholds
+      .iter()
+      .rev()
+      .find(|(threshold, _)| level >= *threshold)
+      .map_or(Kind::Note, |(_, kind)| *kind)
+  }
+}
+
+/// ESLint's convention: 1 is a warning, 2 is an error.
+impl Default for SeverityMap {
+  fn default() -> Self {
+    SeverityMap {
+      thresholds: vec![(0, Kind::Warning), (2, Kind::Error)],
+    }
+  }
+}
+
+/// Comma separated thresholds such as `warning=1,error=3`.
+impl FromStr for SeverityMap {
+  type Err = String;
+
+  fn from_str(value: &str) -> Result<Self, Self::Err> {
+    let mut thresholds = value
+      .split(',')
+      .map(|pair| {
+        let mut parts = pair.splitn(2, '=');
+        match (parts.next().map(kind), parts.next().map(str::parse)) {
+          (Some(Some(kind)), Some(Ok(threshold))) => Ok((threshold, kind)),
+          _ => Err(format!("invalid severity threshold: {}", pair)),
+        }
+      })
+      .collect::<Result<Vec<_>, _>>()?;
+    thresholds.sort();
+    Ok(SeverityMap { thresholds })
+  }
+}
+
+fn kind(word: &str) -> Option<Kind> {
+  match word.trim() {
+    "note" => Some(Kind::Note),
+    "warning" => Some(Kind::Warning),
+    "error" => Some(Kind::Error),
+    "fatal" => Some(Kind::Fatal),
+    _ => None,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn test_default_thresholds() {
+    let sut = SeverityMap::default();
+    assert_eq!(
+      (Kind::Warning, Kind::Warning, Kind::Error, Kind::Error),
+      (sut.kind(0), sut.kind(1), sut.kind(2), sut.kind(8))
+    )
+  }
+
+  #[test]
+  fn test_custom_thresholds() {
+    let sut: SeverityMap = "error=3,warning=1".parse().unwrap();
+    assert_eq!(
+      (Kind::Note, Kind::Warning, Kind::Warning, Kind::Error),
+      (sut.kind(0), sut.kind(1), sut.kind(2), sut.kind(3))
+    )
+  }
+
+  #[test]
+  fn test_invalid_threshold() {
+    assert_eq!(
+      Err(String::from("invalid severity threshold: error")),
+      "warning=1,error".parse::<SeverityMap>()
+    )
+  }
+
+  #[test]
+  fn test_unknown_kind() {
+    assert!("bad=1".parse::<SeverityMap>().is_err())
+  }
+}
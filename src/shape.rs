@@ -19,6 +19,11 @@ impl<T> Deref for Shape<T> {
   }
 }
 
+/// Keep in mind this is an approximate size. Also, from my
+/// understanding, this represents the amount of memory needed
+/// by a regex *once compiled*.
+pub const REGEX_MAX_SIZE: usize = 1024 * 128;
+
 /// Final pattern is made multi-line. The pattern ultimately comes
 /// from user input, it is necessary to limit its size.
 impl<T> TryInto<Regex> for Shape<T>
@@ -28,12 +33,7 @@ where
   type Error = Error;
 
   fn try_into(self) -> Result<Regex, Error> {
-    TryInto::<String>::try_into(self).and_then(|p| {
-      RegexBuilder::new(&p)
-        .size_limit(Self::REGEX_MAX_SIZE)
-        .multi_line(true)
-        .build()
-    })
+    self.compile(REGEX_MAX_SIZE)
   }
 }
 
@@ -70,16 +70,21 @@ impl<T> Shape<T>
 where
   T: Clone + TryInto<Regex, Error = Error>,
 {
-  /// Keep in mind this is an approximate size. Also, from my
-  /// understanding, this represents the amount of memory needed
-  /// by a regex *once compiled*.
-  const REGEX_MAX_SIZE: usize = 1024 * 128;
-
   /// Initialize a new shape, empty by default. This must match nothing.
   pub fn new() -> Self {
     Self(Vec::new())
   }
 
+  /// Same as the conversion to a regex, with a limit of one's own.
+  pub fn compile(self, size_limit: usize) -> Result<Regex, Error> {
+    TryInto::<String>::try_into(self).and_then(|p| {
+      RegexBuilder::new(&p)
+        .size_limit(size_limit)
+        .multi_line(true)
+        .build()
+    })
+  }
+
   /// Add a token to the parser shape.
   pub fn push(self, token: T) -> Self {
     Self([self.to_vec(), vec![token]].concat())
@@ -105,7 +110,18 @@ mod tests {
       .push(Token::Wildcard)
       .push(Token::Message);
     let actual: Regex = sut.try_into().unwrap();
-    let expected = r"(\[Linter\]: )([^\x00]+?)(\d+)(\d+)( )(\b[a-zA-Z]+\b)( )(\s+)(.*?)([^\n]+)";
+    let expected = r"(?:\[Linter\]: )([^\x00\n]+?)(\d+)(\d+)(?: )(\b(?i:internal compiler error|internal error|fatal error|parse error|warning|error|note|fatal|panic|internal)\b|(?:✖|✗|✘|❌|⚠|ℹ)\x{fe0f}?)(?: )(?:\s+)(?:.*?)([^\n]+)";
+    assert_eq!(expected, actual.to_string())
+  }
+
+  #[test]
+  fn test_separated_numbers_regex_as_string() {
+    let sut = Shape::new()
+      .push(Token::Line)
+      .push(Token::Literal(String::from(":")))
+      .push(Token::Column);
+    let actual: Regex = sut.try_into().unwrap();
+    let expected = r"(\d+)(?::)(\d+)";
     assert_eq!(expected, actual.to_string())
   }
 }
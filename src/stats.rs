@@ -0,0 +1,239 @@
+use std::fmt;
+use std::time::Duration;
+
+/// Coverage of the input by the errorformat string, mostly useful to
+/// tell whether a format works at all. A line counts as matched as soon
+/// as any part of it belongs to a match. Bytes give a finer score, e.g.
+/// to rank candidate formats against each other. Timings vary from run
+/// to run so they are left out of comparisons.
+#[derive(Debug, Clone, Default)]
+pub struct ParseStats {
+  pub entries: usize,
+  pub lines: usize,
+  pub matched_lines: usize,
+  pub bytes: usize,
+  pub matched_bytes: usize,
+  pub timings: Timings,
+}
+
+/// Time spent compiling the errorformat string and matching the input
+/// with it: this tells a pathological errorformat string from merely
+/// large input.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Timings {
+  pub compile: Duration,
+  pub matching: Duration,
+}
+
+impl ParseStats {
+  pub fn unmatched_lines(&self) -> usize {
+    self.lines - self.matched_lines
+  }
+
+  /// Where the time went, for `--profile`.
+  pub fn profile(&self) -> String {
+    format!(
+      "compiled in {:?}, matched {} entries in {:?}",
+      self.timings.compile, self.entries, self.timings.matching
+    )
+  }
+
+  /// Share of the input bytes consumed by matches, between 0 and 1.
+  /// Empty input is not covered at all.
+  pub fn coverage(&self) -> f64 {
+    match self.bytes {
+      0 => 0.0,
+      bytes => self.matched_bytes as f64 / bytes as f64,
+    }
+  }
+}
+
+impl PartialEq for ParseStats {
+  fn eq(&self, other: &Self) -> bool {
+    (
+      self.entries,
+      self.lines,
+      self.matched_lines,
+      self.bytes,
+      self.matched_bytes,
+    ) == (
+      other.entries,
+      other.lines,
+      other.matched_lines,
+      other.bytes,
+      other.matched_bytes,
+    )
+  }
+}
+
+/// Stats being gathered along with where the previous match left off.
+pub(crate) struct Tally {
+  stats: ParseStats,
+  cursor: usize,
+  line: usize,
+  last_line: Option<usize>,
+}
+
+impl Tally {
+  pub(crate) fn new(input: &str) -> Self {
+    Tally {
+      stats: ParseStats {
+        lines: input.lines().count(),
+        bytes: input.len(),
+        ..ParseStats::default()
+      },
+      cursor: 0,
+      line: 0,
+      last_line: None,
+    }
+  }
+
+  /// Account for a match spanning the given byte range. Matches come
+  /// in order so lines are only ever counted forward from the previous
+  /// match.
+  pub(crate) fn record(&mut self, input: &str, (start, end): (usize, usize)) {
+    let last = if end > start { end - 1 } else { start };
+    let input = input.as_bytes();
+    self.line += count_lines(&input[self.cursor..start]);
+    let first_line = self.line;
+    self.line += count_lines(&input[start..last]);
+    let first_line = match self.last_line {
+      Some(line) if line >= first_line => line + 1,
+      _ => first_line,
+    };
+    let stats = &mut self.stats;
+    if self.line >= first_line {
+      stats.matched_lines += self.line - first_line + 1;
+    }
+    stats.matched_lines = stats.matched_lines.min(stats.lines);
+    self.cursor = last;
+    self.last_line = Some(self.line);
+    stats.matched_bytes += end - start;
+    stats.entries += 1;
+  }
+
+  pub(crate) fn finish(self, timings: Timings) -> ParseStats {
+    ParseStats {
+      timings,
+      ..self.stats
+    }
+  }
+}
+
+/// Works on bytes: a match may end in the middle of a character.
+fn count_lines(value: &[u8]) -> usize {
+  value.iter().filter(|byte| **byte == b'\n').count()
+}
+
+impl fmt::Display for ParseStats {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    write!(
+      f,
+      "matched {} entries across {} lines, {} lines unmatched, {}/{} bytes matched",
+      self.entries,
+      self.lines,
+      self.unmatched_lines(),
+      self.matched_bytes,
+      self.bytes
+    )
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn stats(input: &str, spans: &[(usize, usize)]) -> ParseStats {
+    spans
+      .iter()
+      .fold(Tally::new(input), |mut acc, span| {
+        acc.record(input, *span);
+        acc
+      })
+      .finish(Timings::default())
+  }
+
+  #[test]
+  fn test_single_line_matches() {
+    let actual = stats("foo\nbar\nbaz\n", &[(0, 4), (8, 11)]);
+    assert_eq!(
+      (2, 3, 2),
+      (actual.entries, actual.lines, actual.matched_lines)
+    )
+  }
+
+  #[test]
+  fn test_multiline_match() {
+    let actual = stats("foo\nbar\nbaz", &[(2, 9)]);
+    assert_eq!(
+      (1, 3, 0),
+      (
+        actual.entries,
+        actual.matched_lines,
+        actual.unmatched_lines()
+      )
+    )
+  }
+
+  #[test]
+  fn test_several_matches_on_a_line() {
+    let actual = stats("a;b;c\nd", &[(0, 2), (2, 4), (4, 6)]);
+    assert_eq!(
+      (3, 1, 1),
+      (
+        actual.entries,
+        actual.matched_lines,
+        actual.unmatched_lines()
+      )
+    )
+  }
+
+  #[test]
+  fn test_match_ending_with_a_multibyte_character() {
+    let actual = stats("é\né", &[(0, 2), (3, 5)]);
+    assert_eq!(2, actual.matched_lines)
+  }
+
+  #[test]
+  fn test_coverage() {
+    let actual = stats("foo\nbar\n", &[(0, 4)]);
+    assert_eq!(
+      (4, 8, 0.5),
+      (actual.matched_bytes, actual.bytes, actual.coverage())
+    )
+  }
+
+  #[test]
+  fn test_empty_input_coverage() {
+    assert_eq!(0.0, stats("", &[]).coverage())
+  }
+
+  #[test]
+  fn test_profile() {
+    let mut actual = stats("foo\nbar", &[(0, 3)]);
+    actual.timings = Timings {
+      compile: Duration::from_micros(1500),
+      matching: Duration::from_millis(20),
+    };
+    assert_eq!(
+      "compiled in 1.5ms, matched 1 entries in 20ms",
+      actual.profile()
+    )
+  }
+
+  #[test]
+  fn test_timings_are_not_compared() {
+    let mut actual = stats("foo\nbar", &[(0, 3)]);
+    actual.timings.matching = Duration::from_millis(20);
+    assert_eq!(stats("foo\nbar", &[(0, 3)]), actual)
+  }
+
+  #[test]
+  fn test_display() {
+    let actual = stats("foo\nbar", &[(0, 3)]);
+    assert_eq!(
+      "matched 1 entries across 2 lines, 1 lines unmatched, 3/7 bytes matched",
+      actual.to_string()
+    )
+  }
+}
@@ -0,0 +1,134 @@
+use crate::entry::Case;
+use crate::entry::Entry;
+use crate::errfmt;
+use crate::token::Token;
+use crate::ErrfmtError;
+use std::str::FromStr;
+
+/// An output template reuses the errorformat placeholders to describe
+/// the shape of a rendered entry. Only placeholders carrying data make
+/// sense here, every other character is copied as is.
+#[derive(Debug, Clone)]
+pub struct Template {
+  tokens: Vec<Token>,
+}
+
+/// Kakoune's format, what lint.kak expects.
+const KAK_TEMPLATE: &str = "%f:%l:%c: %k: %m";
+
+impl Template {
+  /// Substitute the fields of the entry. A missing end line, end column
+  /// or code is rendered as an empty string.
+  pub fn render(&self, entry: &Entry, case: Case) -> String {
+    self.tokens.iter().fold(String::new(), |mut acc, token| {
+      match token {
+        Token::File => acc.push_str(&entry.file),
+        Token::Line => acc.push_str(&entry.line.to_string()),
+        Token::EndLine => acc.push_str(&entry.end_line.map_or(String::new(), |l| l.to_string())),
+        Token::Column => acc.push_str(&entry.column.to_string()),
+        Token::EndColumn => {
+          acc.push_str(&entry.end_column.map_or(String::new(), |c| c.to_string()))
+        }
+        Token::Kind => acc.push_str(&entry.kind.render(case)),
+        Token::Code => acc.push_str(entry.code.as_deref().unwrap_or("")),
+        Token::Message => acc.push_str(&entry.message),
+        Token::Literal(value) => acc.push_str(value),
+        _ => (),
+      };
+      acc
+    })
+  }
+}
+
+impl Default for Template {
+  fn default() -> Self {
+    KAK_TEMPLATE.parse().unwrap()
+  }
+}
+
+impl FromStr for Template {
+  type Err = ErrfmtError;
+
+  fn from_str(template: &str) -> Result<Self, Self::Err> {
+    let tokens: Vec<Token> = errfmt::tokenize(template.to_string())
+      .into_iter()
+      .map(Token::from)
+      .collect();
+    match tokens.iter().find(|token| !renders(token)) {
+      Some(token) => Err(ErrfmtError::UnknownPlaceholder(
+        token
+          .unknown_placeholder()
+          .unwrap_or_else(|| token.to_string()),
+      )),
+      None => Ok(Template { tokens }),
+    }
+  }
+}
+
+/// Whether the token stands for something that can be output.
+fn renders(token: &Token) -> bool {
+  match token {
+    Token::File
+    | Token::Line
+    | Token::EndLine
+    | Token::Column
+    | Token::EndColumn
+    | Token::Kind
+    | Token::Code
+    | Token::Message => true,
+    Token::Literal(_) => token.unknown_placeholder().is_none(),
+    _ => false,
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::entry::Kind;
+
+  fn entry() -> Entry {
+    let mut entry = Entry::new();
+    entry.file = String::from("/tmp/foo");
+    entry.line = 2;
+    entry.column = 3;
+    entry.kind = Kind::Warning;
+    entry.message = String::from("syntax error");
+    entry
+  }
+
+  #[test]
+  fn test_default_template_is_kak_format() {
+    let sut = entry();
+    assert_eq!(
+      sut.to_string(),
+      Template::default().render(&sut, Case::Lower)
+    )
+  }
+
+  #[test]
+  fn test_custom_template() {
+    let sut: Template = "%f|%l|%k|%m".parse().unwrap();
+    assert_eq!(
+      "/tmp/foo|2|WARNING|syntax error",
+      sut.render(&entry(), Case::Upper)
+    )
+  }
+
+  #[test]
+  fn test_literal_percent_sign() {
+    let sut: Template = "%l%%".parse().unwrap();
+    assert_eq!("2%", sut.render(&entry(), Case::Lower))
+  }
+
+  #[test]
+  fn test_placeholder_without_data_is_rejected() {
+    assert_eq!(
+      Some(ErrfmtError::UnknownPlaceholder(String::from("%*"))),
+      "%f%*".parse::<Template>().err()
+    );
+    assert_eq!(
+      Some(ErrfmtError::UnknownPlaceholder(String::from("%x"))),
+      "%f %xyz".parse::<Template>().err()
+    )
+  }
+}
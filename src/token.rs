@@ -1,20 +1,45 @@
+use crate::entry::KIND_PHRASES;
+use crate::entry::KIND_SYMBOLS;
+use crate::entry::KIND_WORDS;
 use regex::Error;
 use regex::Regex;
 use std::convert::From;
 use std::convert::TryInto;
+use std::fmt;
 
 /// A Token is a section of input data. It can be referred to using
 /// pre-defined placeholders that compose an errorformat string.
 #[derive(Debug, Clone)]
 pub enum Token {
   Column,
+  EndColumn,
   File,
+  QuotedFile,
   Kind,
+  Severity,
+  Code,
+  BracketedCode,
   Line,
+  EndLine,
+  Offset,
+  Timestamp,
+  Progress,
   Message,
+  DelimitedMessage,
+  ContinuedMessage,
   Whitespace,
+  Newlines(usize),
+  Indentation,
+  TrailingWhitespace,
+  LooseWhitespace,
+  OptionalWhitespace,
   Wildcard,
+  MultilineWildcard,
+  Separator,
   Literal(String),
+  Delimiter(String),
+  Alternation(Vec<String>),
+  Override(Box<Token>, String),
 }
 
 /// Human-readable way of representing an expected sequence of
@@ -23,12 +48,41 @@ impl From<&str> for Token {
   fn from(value: &str) -> Self {
     match value {
       "%c" => Self::Column,
+      "%C" => Self::EndColumn,
       "%f" => Self::File,
+      "%q" => Self::QuotedFile,
       "%k" => Self::Kind,
+      "%s" => Self::Severity,
+      "%e" => Self::Code,
       "%l" => Self::Line,
+      "%L" => Self::EndLine,
       "%m" => Self::Message,
+      "%M" => Self::ContinuedMessage,
+      "%o" => Self::Offset,
+      "%T" => Self::Timestamp,
+      "%P" => Self::Progress,
       "%." => Self::Whitespace,
+      "%~" => Self::OptionalWhitespace,
       "%*" => Self::Wildcard,
+      "%+" => Self::MultilineWildcard,
+      "%$" => Self::Separator,
+      "%t" => Self::Literal(String::from("\t")),
+      value if value.starts_with("%(") && value.ends_with(')') => Self::Alternation(
+        value[2..value.len() - 1]
+          .split('|')
+          .map(|value| value.replace("%%", "%"))
+          .collect(),
+      ),
+      value if value.starts_with("%.{") && value.ends_with('}') => {
+        match value[3..value.len() - 1].parse() {
+          Ok(count) => Self::Newlines(count),
+          Err(_) => Self::Literal(dedupe_percent_signs(value)),
+        }
+      }
+      value if is_override(value) => Self::Override(
+        Box::new(Self::from(&value[..2])),
+        value[4..value.len() - 2].to_string(),
+      ),
       value => Self::Literal(dedupe_percent_signs(value)),
     }
   }
@@ -41,20 +95,212 @@ impl From<String> for Token {
   }
 }
 
+impl Token {
+  /// Only tokens holding data end up in a capture group: the others
+  /// are merely skipped and capturing them would slow matching down.
+  pub fn captures(&self) -> bool {
+    match self {
+      Self::Column
+      | Self::EndColumn
+      | Self::File
+      | Self::QuotedFile
+      | Self::Kind
+      | Self::Severity
+      | Self::Code
+      | Self::BracketedCode
+      | Self::Line
+      | Self::EndLine
+      | Self::Offset
+      | Self::Message
+      | Self::DelimitedMessage
+      | Self::ContinuedMessage
+      | Self::Override(_, _) => true,
+      Self::Timestamp
+      | Self::Progress
+      | Self::Whitespace
+      | Self::Newlines(_)
+      | Self::Indentation
+      | Self::TrailingWhitespace
+      | Self::LooseWhitespace
+      | Self::OptionalWhitespace
+      | Self::Wildcard
+      | Self::MultilineWildcard
+      | Self::Separator
+      | Self::Literal(_)
+      | Self::Delimiter(_)
+      | Self::Alternation(_) => false,
+    }
+  }
+
+  /// A literal starting with a percent sign is a placeholder that was
+  /// not recognized (a literal percent sign is deduped beforehand).
+  pub fn unknown_placeholder(&self) -> Option<String> {
+    match self {
+      Self::Literal(value) | Self::Delimiter(value)
+        if value.starts_with('%') && value.len() > 1 =>
+      {
+        Some(value.chars().take(2).collect())
+      }
+      _ => None,
+    }
+  }
+
+  /// A placeholder with a custom regex still fills the same field.
+  pub fn base(&self) -> &Self {
+    match self {
+      Self::Override(token, _) => token,
+      token => token,
+    }
+  }
+
+  /// A custom regex must compile on its own and must not capture
+  /// anything: extra groups would shift those of the following fields.
+  pub fn invalid_override(&self) -> Option<(String, String)> {
+    match self {
+      Self::Override(_, pattern) => match Regex::new(pattern) {
+        Ok(regex) if regex.captures_len() > 1 => Some((
+          self.to_string(),
+          String::from("capture groups are not allowed, use (?:...)"),
+        )),
+        Ok(_) => None,
+        Err(err) => Some((self.to_string(), err.to_string())),
+      },
+      Self::Literal(value) | Self::Delimiter(value) if is_unclosed_override(value) => {
+        Some((value.clone(), String::from("missing closing /)")))
+      }
+      _ => None,
+    }
+  }
+
+  /// Numeric tokens cannot be told apart when nothing separates them.
+  pub fn is_numeric(&self) -> bool {
+    matches!(
+      self.base(),
+      Self::Column | Self::EndColumn | Self::Line | Self::EndLine | Self::Offset | Self::Severity
+    )
+  }
+}
+
+/// Back to the errorformat notation, mostly useful for diagnostics.
+impl fmt::Display for Token {
+  fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+    match self {
+      Self::Column => write!(f, "%c"),
+      Self::EndColumn => write!(f, "%C"),
+      Self::File => write!(f, "%f"),
+      Self::QuotedFile => write!(f, "%q"),
+      Self::Kind => write!(f, "%k"),
+      Self::Severity => write!(f, "%s"),
+      Self::Code => write!(f, "%e"),
+      Self::BracketedCode => write!(f, "[%e]"),
+      Self::Line => write!(f, "%l"),
+      Self::EndLine => write!(f, "%L"),
+      Self::Offset => write!(f, "%o"),
+      Self::Timestamp => write!(f, "%T"),
+      Self::Progress => write!(f, "%P"),
+      Self::Message | Self::DelimitedMessage => write!(f, "%m"),
+      Self::ContinuedMessage => write!(f, "%M"),
+      Self::Whitespace | Self::TrailingWhitespace | Self::LooseWhitespace => write!(f, "%."),
+      Self::Newlines(count) => write!(f, "%.{{{}}}", count),
+      Self::Indentation => write!(f, ""),
+      Self::OptionalWhitespace => write!(f, "%~"),
+      Self::Wildcard => write!(f, "%*"),
+      Self::MultilineWildcard => write!(f, "%+"),
+      Self::Separator => write!(f, "%$"),
+      Self::Literal(value) | Self::Delimiter(value) if value == "%" => write!(f, "%%"),
+      Self::Literal(value) | Self::Delimiter(value) if value == "\t" => write!(f, "%t"),
+      Self::Literal(value) | Self::Delimiter(value) => write!(f, "{}", value),
+      Self::Alternation(values) => write!(f, "%({})", values.join("|").replace('%', "%%")),
+      Self::Override(token, pattern) => write!(f, "{}(/{}/)", token, pattern),
+    }
+  }
+}
+
 /// Regexes that will be involved in extracting text data from the input
-/// stream. POSIX allows any character except null bytes in filename.
+/// stream.
 impl TryInto<Regex> for Token {
   type Error = Error;
   fn try_into(self) -> Result<Regex, Error> {
     match &self {
       Self::Column => mkregex(r"\d+"),
-      Self::File => mkregex(r"[^\x00]+?"),
-      Self::Kind => mkregex(r"\b[a-zA-Z]+\b"),
+      Self::EndColumn => mkregex(r"\d+"),
+      // POSIX allows any character except null bytes in filename, line
+      // breaks are excluded too so that a filename never swallows the
+      // end of a previous record.
+      Self::File => mkregex(r"[^\x00\n]+?"),
+      // Any separator may be quoted, quotes are removed afterwards.
+      Self::QuotedFile => mkregex(r#""[^"\x00\n]+"|[^"\x00\n]+?"#),
+      // Only known kinds, whatever their case, or their symbol. Phrases
+      // such as `fatal error` come first so that they are read whole.
+      Self::Kind => mkregex(&format!(
+        r"\b(?i:{}|{})\b|(?:{})\x{{fe0f}}?",
+        KIND_PHRASES
+          .iter()
+          .map(|(phrase, _)| *phrase)
+          .collect::<Vec<_>>()
+          .join("|"),
+        KIND_WORDS.join("|"),
+        KIND_SYMBOLS
+          .iter()
+          .map(|(symbol, _)| *symbol)
+          .collect::<Vec<_>>()
+          .join("|")
+      )),
+      Self::Severity => mkregex(r"\d+"),
+      // Identifiers such as `TS2322` or `clippy::needless_borrow`.
+      Self::Code => mkregex(r"[a-zA-Z0-9_./:-]+"),
+      // May be missing altogether, brackets included.
+      Self::BracketedCode => mkregex(r"\[[a-zA-Z0-9_./:-]+\]|"),
       Self::Line => mkregex(r"\d+"),
+      Self::EndLine => mkregex(r"\d+"),
+      Self::Offset => mkregex(r"\d+"),
+      // Either ISO 8601 date and time or a bracketed time of day, digits
+      // are ASCII only to keep the compiled regex small.
+      Self::Timestamp => mkgroup(concat!(
+        r"[0-9]{4}-[0-9]{2}-[0-9]{2}[T ][0-9]{2}:[0-9]{2}:[0-9]{2}",
+        r"(?:[.,][0-9]+)?(?:Z|[+-][0-9]{2}:?[0-9]{2})?",
+        r"|\[[0-9]{2}:[0-9]{2}:[0-9]{2}(?:[.,][0-9]+)?\]",
+      )),
+      // Those of ninja (`[3/10]`) or of CMake's makefiles (`[ 42%]`),
+      // padding included.
+      Self::Progress => mkgroup(r"\[ *[0-9]+(?:/[0-9]+|%)\]"),
       Self::Message => mkregex(r"[^\n]+"),
-      Self::Whitespace => mkregex(r"\s+"),
-      Self::Wildcard => mkregex(r".*?"),
-      Self::Literal(value) => mkregex(&regex::escape(&value)),
+      Self::DelimitedMessage => mkregex(r"[^\n]+?"),
+      // Goes on over the following indented lines.
+      Self::ContinuedMessage => mkregex(r"[^\n]+(?:\n[ \t]+[^\n]+)*"),
+      Self::Whitespace => mkgroup(r"\s+"),
+      // Put in front of records when asked to, never leaves the line.
+      Self::Indentation => mkgroup(r"[^\S\n]*"),
+      // Exactly that many line breaks, none meaning that it stays on its
+      // line.
+      Self::Newlines(0) => mkgroup(r"[^\S\n]+"),
+      Self::Newlines(count) => mkgroup(&format!(r"[^\S\n]*(?:\n[^\S\n]*){{{}}}", count)),
+      // Closes a record, may be missing for the very last one.
+      Self::TrailingWhitespace => mkgroup(r"\s+|\z"),
+      // May also skip unrelated lines, the fewest possible.
+      Self::LooseWhitespace => mkgroup(r"\s+|\s*\n(?:[^\n]*\n)*?\s*"),
+      Self::OptionalWhitespace => mkgroup(r"\s*"),
+      // Stays on its line, unlike the multiline one.
+      Self::Wildcard => mkgroup(r".*?"),
+      Self::MultilineWildcard => mkgroup(r"[\s\S]*?"),
+      // Pins the end of a line and swallows the line break so that the
+      // next record starts on a fresh line.
+      Self::Separator => mkgroup(r"$\n?"),
+      Self::Literal(value) => mkgroup(&regex::escape(value)),
+      // A message followed by a literal stops at its first occurrence,
+      // and the literal may be missing at the end of a line: this allows
+      // several records on a single line.
+      Self::Delimiter(value) => mkgroup(&format!("{}|$", regex::escape(value))),
+      // Alternatives are literals too.
+      Self::Alternation(values) => mkgroup(
+        &values
+          .iter()
+          .map(|value| regex::escape(value))
+          .collect::<Vec<_>>()
+          .join("|"),
+      ),
+      // A custom regex replaces the one of its placeholder.
+      Self::Override(_, pattern) => mkregex(pattern),
     }
   }
 }
@@ -64,6 +310,28 @@ fn mkregex(s: &str) -> Result<Regex, Error> {
   Regex::new(&format!("({})", s))
 }
 
+/// Wrap given pattern in a non-capturing group.
+fn mkgroup(s: &str) -> Result<Regex, Error> {
+  Regex::new(&format!("(?:{})", s))
+}
+
+/// A data placeholder followed by its custom regex: `%f(/\S+\.rs/)`.
+fn is_override(value: &str) -> bool {
+  lazy_static! {
+    static ref RE: Regex = Regex::new(r"^%[fqlLcCksemMo]\(/.+/\)$").unwrap();
+  }
+  RE.is_match(value)
+}
+
+/// The tokenizer reads a custom regex up to the end of the errorformat
+/// string when it is never closed.
+fn is_unclosed_override(value: &str) -> bool {
+  lazy_static! {
+    static ref RE: Regex = Regex::new(r"^%[fqlLcCksemMo]\(/").unwrap();
+  }
+  RE.is_match(value) && !is_override(value)
+}
+
 /// The percent sign is used as a placeholder prefix. As a result,
 /// it is necessary to escape it.
 fn dedupe_percent_signs(value: &str) -> String {
@@ -101,6 +369,28 @@ mod tests {
     assert!(!token_matches(Token::File, "\0"))
   }
 
+  #[test]
+  fn test_filename_does_not_span_lines() {
+    let r: Regex = Token::File.try_into().unwrap();
+    assert_eq!(Some(1), r.find("\nbar").map(|m| m.start()))
+  }
+
+  #[test]
+  fn test_code_pattern_match() {
+    assert!(token_matches(Token::Code, "TS2322"));
+    assert!(token_matches(Token::Code, "clippy::needless_borrow"));
+    assert!(!token_matches(Token::Code, " "))
+  }
+
+  #[test]
+  fn test_quoted_filename_pattern_match() {
+    let r: Regex = Token::QuotedFile.try_into().unwrap();
+    assert_eq!(
+      Some(r#""my:weird:file.c""#),
+      r.find(r#""my:weird:file.c":10:5"#).map(|m| m.as_str())
+    )
+  }
+
   #[test]
   fn test_line_number_pattern_match() {
     assert!(token_matches(Token::Line, r"42"))
@@ -111,6 +401,43 @@ mod tests {
     assert!(token_matches(Token::Column, r"42"))
   }
 
+  #[test]
+  fn test_offset_pattern_match() {
+    assert!(token_matches(Token::Offset, r"1337"))
+  }
+
+  #[test]
+  fn test_timestamp_pattern_match() {
+    let tests = vec![
+      "2024-01-02T03:04:05Z",
+      "2024-01-02 03:04:05.123+01:00",
+      "2024-01-02T03:04:05.1234567Z",
+      "[03:04:05]",
+    ];
+    for test in tests {
+      let r: Regex = Token::Timestamp.try_into().unwrap();
+      assert_eq!(Some(test), r.find(test).map(|m| m.as_str()));
+    }
+  }
+
+  #[test]
+  fn test_timestamp_pattern_mismatch() {
+    assert!(!token_matches(Token::Timestamp, "03:04:05"))
+  }
+
+  #[test]
+  fn test_progress_pattern_match() {
+    for test in &["[3/10]", "[ 42%]", "[100%]", "[  7/120]"] {
+      let r: Regex = Token::Progress.try_into().unwrap();
+      assert_eq!(Some(*test), r.find(test).map(|m| m.as_str()));
+    }
+  }
+
+  #[test]
+  fn test_progress_pattern_mismatch() {
+    assert!(!token_matches(Token::Progress, "[E0308]"))
+  }
+
   #[test]
   fn test_line_number_pattern_mismatch() {
     assert!(!token_matches(Token::Line, r"foo"))
@@ -123,12 +450,45 @@ mod tests {
 
   #[test]
   fn test_kind_pattern_match() {
-    assert!(token_matches(Token::Kind, r"anyWord"))
+    assert!(token_matches(Token::Kind, r"Warning"))
+  }
+
+  #[test]
+  fn test_severity_pattern_match() {
+    assert!(token_matches(Token::Severity, r"2"))
+  }
+
+  #[test]
+  fn test_continued_message_pattern_match() {
+    assert!(token_matches(Token::ContinuedMessage, "foo\n  bar"))
+  }
+
+  #[test]
+  fn test_bracketed_code_pattern_match() {
+    assert!(token_matches(Token::BracketedCode, "[E0308]"))
+  }
+
+  #[test]
+  fn test_kind_phrase_pattern_match() {
+    let r: Regex = Token::Kind.try_into().unwrap();
+    assert_eq!(
+      Some("fatal error"),
+      r.find("fatal error: foo.h").map(|m| m.as_str())
+    )
+  }
+
+  #[test]
+  fn test_kind_symbol_pattern_match() {
+    assert!(token_matches(Token::Kind, "✖"));
+    assert!(token_matches(Token::Kind, "⚠\u{fe0f}"));
+    assert!(!token_matches(Token::Kind, "✓"))
   }
 
   #[test]
   fn test_kind_pattern_mismatch() {
-    assert!(!token_matches(Token::Kind, r"[notG00d]"))
+    assert!(!token_matches(Token::Kind, r"[notG00d]"));
+    assert!(!token_matches(Token::Kind, r"anyWord"));
+    assert!(!token_matches(Token::Kind, r"errors"))
   }
 
   #[test]
@@ -141,6 +501,20 @@ mod tests {
     assert!(!token_matches(Token::Whitespace, "abcd"))
   }
 
+  #[test]
+  fn test_newlines_pattern_match() {
+    let r: Regex = Token::Newlines(2).try_into().unwrap();
+    assert_eq!(Some(" \n\t\n "), r.find(" \n\t\n x").map(|m| m.as_str()));
+    assert!(token_matches(Token::Newlines(0), " \t"))
+  }
+
+  #[test]
+  fn test_newlines_pattern_mismatch() {
+    let r: Regex = Token::Newlines(2).try_into().unwrap();
+    assert!(!r.is_match(" \n "));
+    assert!(!token_matches(Token::Newlines(0), "\n"))
+  }
+
   #[test]
   fn test_message_pattern_match() {
     assert!(token_matches(
@@ -167,6 +541,50 @@ mod tests {
     assert!(token_matches(Token::Wildcard, "hello\nworld"))
   }
 
+  #[test]
+  fn test_optional_whitespace_pattern_match() {
+    assert!(token_matches(Token::OptionalWhitespace, "	 \n"))
+  }
+
+  #[test]
+  fn test_optional_whitespace_matches_nothing() {
+    let r: Regex = Token::OptionalWhitespace.try_into().unwrap();
+    assert_eq!(Some(0), r.find("abcd").map(|m| m.end()))
+  }
+
+  #[test]
+  fn test_trailing_whitespace_pattern_match() {
+    assert!(token_matches(Token::TrailingWhitespace, "\n"))
+  }
+
+  #[test]
+  fn test_trailing_whitespace_matches_end_of_input() {
+    let r: Regex = Token::TrailingWhitespace.try_into().unwrap();
+    assert_eq!(Some(4), r.find("abcd").map(|m| m.start()))
+  }
+
+  #[test]
+  fn test_delimited_message_is_not_greedy() {
+    let r: Regex = Token::DelimitedMessage.try_into().unwrap();
+    assert_eq!(Some(1), r.find("foo").map(|m| m.end()))
+  }
+
+  #[test]
+  fn test_delimiter_pattern_match() {
+    assert!(token_matches(Token::Delimiter(String::from(";")), ";"))
+  }
+
+  #[test]
+  fn test_delimiter_matches_end_of_line() {
+    let r: Regex = Token::Delimiter(String::from(";")).try_into().unwrap();
+    assert_eq!(Some(3), r.find("foo").map(|m| m.start()))
+  }
+
+  #[test]
+  fn test_separator_pattern_match() {
+    assert!(token_matches(Token::Separator, "\n"))
+  }
+
   #[test]
   fn test_literal_pattern_match() {
     assert!(token_matches(
@@ -221,6 +639,53 @@ mod tests {
     }
   }
 
+  #[test]
+  fn test_alternation_pattern_match() {
+    let token = Token::from("%(error|fatal error|a.b|100%%)");
+    let r: Regex = token.clone().try_into().unwrap();
+    assert_eq!(
+      Some("fatal error"),
+      r.find("fatal error: foo").map(|m| m.as_str())
+    );
+    assert!(!r.is_match("axb"));
+    assert!(r.is_match("100%"));
+    assert_eq!("%(error|fatal error|a.b|100%%)", token.to_string())
+  }
+
+  #[test]
+  fn test_display_placeholders() {
+    assert_eq!("%l", Token::Line.to_string());
+    assert_eq!("%%", Token::from("%%").to_string());
+  }
+
+  #[test]
+  fn test_unknown_placeholder() {
+    assert_eq!(
+      Some(String::from("%x")),
+      Token::from("%xyz").unknown_placeholder()
+    );
+    assert_eq!(None, Token::from("%%").unknown_placeholder());
+    assert_eq!(None, Token::from("xyz").unknown_placeholder());
+  }
+
+  #[test]
+  fn test_tab_is_a_literal() {
+    let token = Token::from("%t");
+    assert!(token_matches(token.clone(), "\t"));
+    assert!(!token_matches(token.clone(), " "));
+    assert_eq!("%t", token.to_string())
+  }
+
+  #[test]
+  fn test_regex_override() {
+    let token = Token::from(r"%f(/\S+\.rs/)");
+    assert!(matches!(token.base(), Token::File));
+    assert!(token.captures());
+    assert!(token_matches(token.clone(), "src/main.rs"));
+    assert!(!token_matches(token.clone(), "main.c"));
+    assert_eq!(r"%f(/\S+\.rs/)", token.to_string())
+  }
+
   #[test]
   fn test_from_dedupes_percent_signs() {
     if let Token::Literal(actual) = Token::from("%%") {
@@ -0,0 +1,10 @@
+use wasm_bindgen::prelude::*;
+
+/// Same as `run` with entries joined by line breaks. Errors are thrown
+/// as strings on the JavaScript side.
+#[wasm_bindgen(js_name = run)]
+pub fn run_js(input: &str, errfmt: &str, file: &str) -> Result<String, JsValue> {
+  crate::run(input, errfmt, file)
+    .map(|lines| lines.join("\n"))
+    .map_err(|err| JsValue::from_str(&err.to_string()))
+}
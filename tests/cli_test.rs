@@ -0,0 +1,131 @@
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Command;
+use std::process::Stdio;
+
+/// A throwaway git repository holding `a.c`, whose second line is
+/// changed after the first commit.
+fn repository(name: &str) -> PathBuf {
+  let dir = env::temp_dir().join(format!("errfmt-{}-{}", name, std::process::id()));
+  let _ = fs::remove_dir_all(&dir);
+  fs::create_dir_all(&dir).unwrap();
+  fs::write(dir.join("a.c"), "int a;\nint b;\nint c;\n").unwrap();
+  git(&dir, &["init", "-q"]);
+  git(&dir, &["add", "a.c"]);
+  git(
+    &dir,
+    &[
+      "-c",
+      "user.name=a",
+      "-c",
+      "user.email=a@a",
+      "commit",
+      "-qm",
+      "a",
+    ],
+  );
+  fs::write(dir.join("a.c"), "int a;\nint B;\nint c;\n").unwrap();
+  dir
+}
+
+fn git(dir: &Path, args: &[&str]) {
+  let status = Command::new("git")
+    .args(args)
+    .current_dir(dir)
+    .status()
+    .unwrap();
+  assert!(status.success());
+}
+
+fn errfmt(dir: &Path, args: &[&str], input: &str) -> String {
+  run(dir, args, input).0
+}
+
+/// Standard output and standard error of a run.
+fn run(dir: &Path, args: &[&str], input: &str) -> (String, String) {
+  let mut child = Command::new(env!("CARGO_BIN_EXE_errfmt"))
+    .args(args)
+    .current_dir(dir)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::piped())
+    .spawn()
+    .unwrap();
+  child
+    .stdin
+    .take()
+    .unwrap()
+    .write_all(input.as_bytes())
+    .unwrap();
+  let output = child.wait_with_output().unwrap();
+  (
+    String::from_utf8(output.stdout).unwrap(),
+    String::from_utf8(output.stderr).unwrap(),
+  )
+}
+
+#[test]
+fn test_changed_only_with_fallback_file() {
+  let dir = repository("fallback");
+  let actual = errfmt(
+    &dir,
+    &[
+      "-e",
+      "%l: %m",
+      "--file",
+      "a.c",
+      "--file-mode",
+      "fallback",
+      "--changed-only",
+    ],
+    "1: old\n2: new\n",
+  );
+  fs::remove_dir_all(&dir).unwrap();
+  assert_eq!("a.c:2:1: error: new\n", actual);
+}
+
+#[test]
+fn test_changed_only_with_offsets() {
+  let dir = repository("offsets");
+  let actual = errfmt(
+    &dir,
+    &[
+      "-e",
+      "%o: %m",
+      "--file",
+      "a.c",
+      "--file-mode",
+      "fallback",
+      "--changed-only",
+    ],
+    "0: old\n7: new\n",
+  );
+  fs::remove_dir_all(&dir).unwrap();
+  assert_eq!("a.c:2:1: error: new\n", actual);
+}
+
+#[test]
+fn test_exclude_file_rejects_other_formats() {
+  let (output, error) = run(
+    &env::temp_dir(),
+    &[
+      "-p",
+      "passthrough",
+      "--exclude-file",
+      "known",
+      "--format",
+      "jsonl",
+    ],
+    "a.c:1:1: error: foo\n",
+  );
+  assert_eq!(
+    (
+      "",
+      "--exclude-file cannot read back entries printed with --format jsonl\n"
+    ),
+    (output.as_str(), error.as_str())
+  );
+}
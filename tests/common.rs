@@ -1,10 +1,33 @@
 use std::fs;
 
-pub fn run_snapshot(name: &str, errfmt: &str) -> () {
+pub fn run_snapshot(name: &str, errfmt: &str) {
   check_snapshot(read_snapshot(name), errfmt.to_string());
 }
 
-fn check_snapshot((input, expected): (String, String), errfmt: String) -> () {
+pub fn run_snapshot_with(name: &str, config: &errfmt::Config) {
+  let (input, expected) = read_snapshot(name);
+  assert_eq!(
+    expected,
+    errfmt::run_with(input, config).unwrap().join("\n")
+  );
+}
+
+/// Entries are read from the input by the given closure, which may
+/// pre-process it or read it with something else than an errorformat
+/// string, then rendered the way `errfmt::run` does.
+pub fn run_snapshot_entries(name: &str, parse: impl Fn(String) -> Vec<errfmt::Entry>) {
+  let (input, expected) = read_snapshot(name);
+  assert_eq!(
+    expected,
+    parse(input)
+      .into_iter()
+      .map(|entry| entry.clamp(1).to_string())
+      .collect::<Vec<_>>()
+      .join("\n")
+  );
+}
+
+fn check_snapshot((input, expected): (String, String), errfmt: String) {
   assert_eq!(
     expected,
     errfmt::run(input, errfmt, String::new())
@@ -0,0 +1,74 @@
+extern crate errfmt;
+
+use errfmt::Entry;
+use errfmt::Kind;
+use proptest::prelude::*;
+
+/// Only what Kakoune's format can carry without loss: paths without
+/// colons, no ranges nor codes, and kinds that render as themselves.
+fn entry() -> impl Strategy<Value = Entry> {
+  (
+    "[^:\n\x00]{1,20}",
+    1..u32::MAX,
+    1..u32::MAX,
+    prop_oneof![Just(Kind::Warning), Just(Kind::Error)],
+    "[^\n]{1,40}",
+  )
+    .prop_map(|(file, line, column, kind, message)| Entry::with(file, line, column, kind, message))
+}
+
+proptest! {
+  #[test]
+  fn test_rendered_entries_parse_back(entries in prop::collection::vec(entry(), 0..8)) {
+    let input = entries
+      .iter()
+      .map(Entry::to_string)
+      .collect::<Vec<_>>()
+      .join("\n");
+    let actual = errfmt::parse(input, errfmt::PASSTHROUGH_ERRFMT, "").unwrap();
+    prop_assert_eq!(entries, actual);
+  }
+}
+
+/// Anything the parsers may produce, including what Kakoune's format
+/// renders with loss: notes, fatal errors, codes and ranges.
+fn any_entry() -> impl Strategy<Value = Entry> {
+  (
+    entry(),
+    prop_oneof![
+      Just(Kind::Note),
+      Just(Kind::Warning),
+      Just(Kind::Error),
+      Just(Kind::Fatal)
+    ],
+    "[^\n\x00]{1,20}",
+    prop::option::of("[A-Za-z0-9\\[\\] ]{1,8}"),
+    prop::option::of(1..u32::MAX),
+    prop::option::of(1..u32::MAX),
+  )
+    .prop_map(|(mut entry, kind, file, code, end_line, end_column)| {
+      entry.kind = kind;
+      entry.file = file;
+      entry.code = code;
+      entry.end_line = end_line;
+      entry.end_column = end_column;
+      entry
+    })
+}
+
+fn render(entries: &[Entry]) -> String {
+  entries
+    .iter()
+    .map(Entry::to_string)
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+proptest! {
+  #[test]
+  fn test_passthrough_is_idempotent(entries in prop::collection::vec(any_entry(), 0..8)) {
+    let once = render(&entries);
+    let twice = render(&errfmt::parse(once.clone(), errfmt::PASSTHROUGH_ERRFMT, "").unwrap());
+    prop_assert_eq!(once, twice);
+  }
+}
@@ -37,6 +37,84 @@ fn test_rustfmt_error_with_code() {
   common::run_snapshot("rustfmt-error-with-code", errfmt::RUSTFMT_ERRFMT);
 }
 
+#[test]
+fn test_rustc_error_with_code() {
+  common::run_snapshot("rustc-error-with-code", errfmt::RUSTC_ERRFMT);
+}
+
+#[test]
+fn test_rustc_warning_without_code() {
+  common::run_snapshot("rustc-warning-without-code", errfmt::RUSTC_ERRFMT);
+}
+
+#[test]
+fn test_cargo_check_json() {
+  common::run_snapshot_entries("cargo-check-json", |input| errfmt::parse_json(input, ""));
+}
+
+#[test]
+fn test_pyright_lsp() {
+  common::run_snapshot_entries("pyright-lsp", |input| errfmt::parse_lsp(input, ""));
+}
+
+#[test]
+fn test_git_diff() {
+  common::run_snapshot_entries("git-diff", |input| errfmt::parse_diff(input, ""));
+}
+
+#[test]
+fn test_grep() {
+  common::run_snapshot_with(
+    "grep",
+    &errfmt::Config::new(errfmt::GREP_ERRFMT).default_kind(errfmt::Kind::Note),
+  );
+}
+
+#[test]
+fn test_git_grep() {
+  common::run_snapshot_with(
+    "git-grep",
+    &errfmt::Config::new(errfmt::GREP_ERRFMT).default_kind(errfmt::Kind::Note),
+  );
+}
+
+#[test]
+fn test_ripgrep_vimgrep() {
+  common::run_snapshot_with(
+    "ripgrep-vimgrep",
+    &errfmt::Config::new(errfmt::RIPGREP_ERRFMT).default_kind(errfmt::Kind::Note),
+  );
+}
+
+#[test]
+fn test_ripgrep_heading() {
+  common::run_snapshot_entries("ripgrep-heading", |input| {
+    let config = errfmt::Config::new(errfmt::RIPGREP_ERRFMT).default_kind(errfmt::Kind::Note);
+    errfmt::parse_with(errfmt::expand_headings(input), &config).unwrap()
+  });
+}
+
+#[test]
+fn test_gcc_color() {
+  common::run_snapshot_entries("gcc-color", |input| {
+    errfmt::parse(errfmt::strip_ansi(input), errfmt::PASSTHROUGH_ERRFMT, "").unwrap()
+  });
+}
+
+#[test]
+fn test_clippy_color() {
+  common::run_snapshot_entries("clippy-color", |input| {
+    errfmt::parse(errfmt::strip_ansi(input), errfmt::RUSTC_ERRFMT, "").unwrap()
+  });
+}
+
+#[test]
+fn test_eslint_color() {
+  common::run_snapshot_entries("eslint-color", |input| {
+    errfmt::parse(errfmt::strip_ansi(input), errfmt::ESLINT_ERRFMT, "").unwrap()
+  });
+}
+
 #[test]
 fn test_eslint_error() {
   common::run_snapshot("eslint-error", errfmt::ESLINT_ERRFMT);
@@ -47,6 +125,17 @@ fn test_eslint_warning() {
   common::run_snapshot("eslint-warning", errfmt::ESLINT_ERRFMT);
 }
 
+#[test]
+fn test_eslint_squeezed() {
+  common::run_snapshot_entries("eslint-squeezed", |input| {
+    errfmt::parse(input, errfmt::ESLINT_ERRFMT, "")
+      .unwrap()
+      .into_iter()
+      .map(errfmt::Entry::squeeze_whitespace)
+      .collect()
+  });
+}
+
 #[test]
 fn test_golint_error() {
   common::run_snapshot("golint-error", errfmt::GOLINT_ERRFMT);
@@ -61,3 +150,54 @@ fn test_shellcheck_error() {
 fn test_shellcheck_note() {
   common::run_snapshot("shellcheck-note", errfmt::SHELLCHECK_ERRFMT);
 }
+
+#[test]
+fn test_tsc_error() {
+  common::run_snapshot("tsc-error", errfmt::TSC_ERRFMT);
+}
+
+#[test]
+fn test_bison_dot_column() {
+  common::run_snapshot("bison-dot-column", "%f:%l.%c: %k: %m%$");
+}
+
+#[test]
+fn test_paren_column() {
+  common::run_snapshot("paren-column", "%f:%l:(%c): %k: %m%$");
+}
+
+#[test]
+fn test_go_test_indented() {
+  common::run_snapshot_with(
+    "go-test-indented",
+    &errfmt::Config::new("%f:%l: %m").ignore_indent(true),
+  );
+}
+
+#[test]
+fn test_ninja_progress() {
+  common::run_snapshot("ninja-progress", "%P %f:%l:%c: %k: %m");
+}
+
+#[test]
+fn test_ruff_column_range() {
+  common::run_snapshot("ruff-column-range", "%f:%l:%c-%C: %m%$");
+}
+
+#[test]
+fn test_ruff_column_range_kak_range() {
+  common::run_snapshot_with(
+    "ruff-column-range-kak-range",
+    &errfmt::Config::new("%f:%l:%c-%C: %m%$").output(errfmt::Format::KakRange),
+  );
+}
+
+#[test]
+fn test_line_range() {
+  common::run_snapshot("line-range", "%f:%l,%L: %k: %m%$");
+}
+
+#[test]
+fn test_ghc_wrapped_message() {
+  common::run_snapshot("ghc-wrapped-message", "%f:%l:%c: %k:%*%$%.%M");
+}